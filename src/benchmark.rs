@@ -0,0 +1,114 @@
+/*
+ * Timing breakdown for --benchmark.
+ *
+ * Time is added up per phase over the whole crawl. The blocking reqwest client has no hooks into
+ * name resolution, connecting or the TLS handshake, and a new client and connection are made for
+ * every request, so those are measured together with the wait for the first byte of the response
+ * as "request", from sending it until its headers have arrived. Name resolution is part of
+ * "client setup" instead when --dns-cache or --resolver look hosts up. Parsing and extraction run
+ * from the start of reading a fetched body to the end of its page, with the pages it links to
+ * left out, as they are crawled in the middle of it. Whatever the phases leave of the crawl's wall
+ * clock time is listed as "other", such as writing --dump-dir pages or rendering with --render.
+ */
+
+use std::time::Duration;
+
+#[derive(Clone, Copy)]
+pub enum Phase {
+    Setup,
+    Wait,
+    Request,
+    Download,
+    Parse,
+}
+
+#[derive(Default)]
+pub struct Timings {
+    setup: Duration,
+    wait: Duration,
+    request: Duration,
+    download: Duration,
+    parse: Duration,
+    requests: u32,
+}
+
+impl Timings {
+    pub fn add(&mut self, phase: Phase, elapsed: Duration) {
+        match phase {
+            Phase::Setup => self.setup += elapsed,
+            Phase::Wait => self.wait += elapsed,
+            Phase::Request => {
+                self.request += elapsed;
+                self.requests += 1;
+            }
+            Phase::Download => self.download += elapsed,
+            Phase::Parse => self.parse += elapsed,
+        }
+    }
+
+    pub fn summary(&self, crawl: Duration, pages: usize) -> Vec<String> {
+        let measured = self.setup + self.wait + self.request + self.download + self.parse;
+        let other = crawl.saturating_sub(measured);
+        let total = crawl.as_secs_f64().max(f64::EPSILON);
+        let mut lines = vec![format!(
+            "Benchmark: {} pages parsed from {} requests in {:.3}s, {:.1} pages/s",
+            pages,
+            self.requests,
+            crawl.as_secs_f64(),
+            pages as f64 / total
+        )];
+        for (name, duration, note) in [
+            ("client setup", self.setup, ""),
+            ("politeness waits", self.wait, ""),
+            (
+                "request",
+                self.request,
+                "DNS, connect, TLS and time to first byte",
+            ),
+            ("download", self.download, ""),
+            ("parse and extract", self.parse, ""),
+            ("other", other, "the rest of the crawl"),
+        ] {
+            let mut line = format!(
+                "  {:<18} {:>9.3}s {:>5.1}%",
+                name,
+                duration.as_secs_f64(),
+                duration.as_secs_f64() / total * 100.0
+            );
+            if !note.is_empty() {
+                line.push_str(&format!("  ({})", note));
+            }
+            lines.push(line);
+        }
+        if self.requests > 0 {
+            lines.push(format!(
+                "  {:<18} {:>9.1}ms",
+                "request average",
+                self.request.as_secs_f64() * 1000.0 / self.requests as f64
+            ));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmeasured_time_is_listed_as_other() {
+        let mut timings = Timings::default();
+        timings.add(Phase::Request, Duration::from_millis(300));
+        timings.add(Phase::Parse, Duration::from_millis(500));
+        let summary = timings.summary(Duration::from_secs(1), 1);
+        let line = |name: &str| {
+            summary
+                .iter()
+                .find(|line| line.trim_start().starts_with(name))
+                .cloned()
+                .unwrap_or_default()
+        };
+        assert!(line("parse and extract").contains("0.500s"));
+        assert!(line("other").contains("0.200s"));
+    }
+}
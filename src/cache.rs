@@ -0,0 +1,132 @@
+/*
+ * On-disk cache of validators (ETag / Last-Modified) and bodies for conditional re-crawls.
+ *
+ * Entries are keyed by the full URL and are only written for 200 responses that carry at least
+ * one validator and were read in full, not cut off by --max-body-size. A fresh 200 response
+ * overwrites the stored entry, a 304 response reuses the stored body, and an entry whose files
+ * are missing or unreadable is ignored so the page is fetched in full. There is no expiry:
+ * delete the cache directory (or individual files in it) to invalidate.
+ */
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use reqwest::{
+    header::{
+        HeaderMap, HeaderName, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+    },
+    Url,
+};
+
+pub struct CacheEntry {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl CacheEntry {
+    pub fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = self
+            .etag
+            .as_deref()
+            .and_then(|v| HeaderValue::from_str(v).ok())
+        {
+            headers.insert(IF_NONE_MATCH, value);
+        }
+        if let Some(value) = self
+            .last_modified
+            .as_deref()
+            .and_then(|v| HeaderValue::from_str(v).ok())
+        {
+            headers.insert(IF_MODIFIED_SINCE, value);
+        }
+        headers
+    }
+}
+
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn open(dir: &Path) -> io::Result<Cache> {
+        fs::create_dir_all(dir)?;
+        Ok(Cache {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    fn paths(&self, url: &Url) -> (PathBuf, PathBuf) {
+        let key = cache_key(url);
+        (
+            self.dir.join(format!("{}.meta", key)),
+            self.dir.join(format!("{}.body", key)),
+        )
+    }
+
+    pub fn load(&self, url: &Url) -> Option<CacheEntry> {
+        let (meta_path, body_path) = self.paths(url);
+        let meta = fs::read_to_string(meta_path).ok()?;
+        let mut lines = meta.lines();
+
+        // The stored URL guards against two URLs hashing to the same key
+        if lines.next()? != url.as_str() {
+            return None;
+        }
+        let etag = lines
+            .next()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string);
+        let last_modified = lines
+            .next()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string);
+        let body = fs::read(body_path).ok()?;
+
+        Some(CacheEntry {
+            etag,
+            last_modified,
+            body,
+        })
+    }
+
+    pub fn store(&self, url: &Url, headers: &HeaderMap, body: &[u8]) -> io::Result<()> {
+        let etag = header_string(headers, ETAG);
+        let last_modified = header_string(headers, LAST_MODIFIED);
+        if etag.is_none() && last_modified.is_none() {
+            return Ok(());
+        }
+
+        let (meta_path, body_path) = self.paths(url);
+        fs::write(body_path, body)?;
+        fs::write(
+            meta_path,
+            format!(
+                "{}\n{}\n{}\n",
+                url,
+                etag.unwrap_or_default(),
+                last_modified.unwrap_or_default()
+            ),
+        )
+    }
+}
+
+fn header_string(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+// FNV-1a, used instead of std's hasher so keys stay stable between builds
+pub fn cache_key(url: &Url) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in url.as_str().bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
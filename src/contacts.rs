@@ -0,0 +1,242 @@
+/*
+ * Extraction of email addresses and social media accounts.
+ *
+ * Social accounts are recognised from URLs on a fixed list of networks and reduced to a
+ * canonical https://<network>/<account> form, so the many ways of linking to the same profile
+ * (www./m. prefixes, trailing paths, query strings) collapse into one entry.
+ *
+ * Email matches that are obviously not addresses are dropped, such as asset names like
+ * logo@2x.png whose "top level domain" is a file extension, or addresses with empty or
+ * hyphen-edged labels. Internationalised domains are matched in both their Unicode and
+ * punycode (xn--) forms and reported in Unicode, so the two spellings of an address are one.
+ *
+ * With deobfuscation on, "(at)", "[at]", "{at}" and the matching "dot" forms, as well as the
+ * character references &#64; and &#46; left in text by double escaping, are turned back into
+ * @ and . before matching, so "name (at) example (dot) com" is found as name@example.com. Plain
+ * " at " and " dot " occur in ordinary prose far too often to be taken on their own, so they are
+ * only read as an address when both make one up, as in "name at example dot com".
+ */
+
+use std::borrow::Cow;
+
+use regex::Regex;
+use reqwest::Url;
+
+const SOCIAL_HOSTS: &[&str] = &[
+    "twitter.com",
+    "x.com",
+    "facebook.com",
+    "instagram.com",
+    "linkedin.com",
+    "github.com",
+    "youtube.com",
+    "tiktok.com",
+    "reddit.com",
+    "pinterest.com",
+    "threads.net",
+    "mastodon.social",
+];
+
+// First path segments that are site features rather than accounts
+const IGNORED_SEGMENTS: &[&str] = &[
+    "share",
+    "sharer",
+    "sharer.php",
+    "intent",
+    "home",
+    "login",
+    "search",
+    "hashtag",
+    "explore",
+    "watch",
+    "embed",
+    "pages",
+    "groups",
+    "events",
+    "about",
+    "help",
+    "legal",
+    "privacy",
+    "tos",
+];
+
+// File extensions that the email pattern would otherwise take for a top level domain
+const FILE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "avif", "bmp", "ico", "tif", "tiff", "css", "js",
+    "json", "xml", "pdf", "zip", "gz", "mp3", "mp4", "webm", "mov", "woff", "woff2", "ttf", "eot",
+    "otf", "php", "html", "htm", "aspx", "jsp",
+];
+
+pub fn plausible_email(email: &str) -> bool {
+    let (local, domain) = match email.rsplit_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let tld = domain.rsplit('.').next().unwrap_or_default();
+    !local.is_empty()
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && domain.contains('.')
+        && domain
+            .split('.')
+            .all(|label| !label.is_empty() && !label.starts_with('-') && !label.ends_with('-'))
+        && !FILE_EXTENSIONS.contains(&tld.to_ascii_lowercase().as_str())
+}
+
+// Lowercase, with the domain in its Unicode form
+pub fn canonical_email(email: &str) -> String {
+    let email = email.to_lowercase();
+    match email.rsplit_once('@') {
+        Some((local, domain)) => match idna::domain_to_unicode(domain) {
+            (domain, Ok(())) => format!("{}@{}", local, domain),
+            (_, Err(_)) => email,
+        },
+        None => email,
+    }
+}
+
+pub struct ContactMatcher {
+    email: Regex,
+    url: Regex,
+    // Only set with deobfuscation on
+    at: Option<Regex>,
+    dot: Option<Regex>,
+    spelled: Option<Regex>,
+    spelled_dot: Option<Regex>,
+}
+
+impl ContactMatcher {
+    pub fn new(deobfuscate: bool) -> ContactMatcher {
+        ContactMatcher {
+            email: Regex::new(
+                r"\b[A-Za-z0-9._%+-]+@[\p{L}\p{N}-]+(?:\.[\p{L}\p{N}-]+)*\.(?:\p{L}{2,}|xn--[A-Za-z0-9-]+)\b",
+            )
+            .unwrap(),
+            url: Regex::new(r#"https?://[^\s"'<>()]+"#).unwrap(),
+            at: deobfuscate.then(|| {
+                Regex::new(r"(?i)\s*(?:[(\[{]\s*at\s*[)\]}]|&#0*64;|&#x0*40;)\s*").unwrap()
+            }),
+            dot: deobfuscate.then(|| {
+                Regex::new(r"(?i)\s*(?:[(\[{]\s*dot\s*[)\]}]|&#0*46;|&#x0*2e;)\s*").unwrap()
+            }),
+            spelled: deobfuscate.then(|| {
+                Regex::new(
+                    r"(?i)\b([A-Za-z0-9._%+-]+)\s+at\s+((?:[\p{L}\p{N}-]+\s+dot\s+)+\p{L}{2,})\b",
+                )
+                .unwrap()
+            }),
+            spelled_dot: deobfuscate.then(|| Regex::new(r"(?i)\s+dot\s+").unwrap()),
+        }
+    }
+
+    pub fn find_emails(&self, text: &str) -> Vec<String> {
+        let text = match (&self.at, &self.dot, &self.spelled, &self.spelled_dot) {
+            (Some(at), Some(dot), Some(spelled), Some(spelled_dot)) => {
+                let text = spelled.replace_all(text, |parts: &regex::Captures| {
+                    format!("{}@{}", &parts[1], spelled_dot.replace_all(&parts[2], "."))
+                });
+                Cow::Owned(
+                    dot.replace_all(&at.replace_all(&text, "@"), ".")
+                        .into_owned(),
+                )
+            }
+            _ => Cow::Borrowed(text),
+        };
+        self.email
+            .find_iter(&text)
+            .map(|email| canonical_email(email.as_str()))
+            .filter(|email| plausible_email(email))
+            .collect()
+    }
+
+    pub fn find_socials(&self, text: &str) -> Vec<String> {
+        self.url
+            .find_iter(text)
+            .filter_map(|candidate| Url::parse(candidate.as_str()).ok())
+            .filter_map(|url| social_account(&url))
+            .collect()
+    }
+}
+
+pub fn social_account(url: &Url) -> Option<String> {
+    let host = url.host_str()?.to_lowercase();
+    let host = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(&host);
+    if !SOCIAL_HOSTS.contains(&host) {
+        return None;
+    }
+
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let account = match (host, segments.as_slice()) {
+        ("linkedin.com", [kind @ ("in" | "company"), name, ..])
+        | ("reddit.com", [kind @ ("user" | "u" | "r"), name, ..])
+        | ("youtube.com", [kind @ ("c" | "channel" | "user"), name, ..]) => {
+            format!("{}/{}", kind, name)
+        }
+        ("linkedin.com" | "reddit.com", _) => return None,
+        (_, [name, ..]) if !IGNORED_SEGMENTS.contains(&name.to_lowercase().as_str()) => {
+            name.to_string()
+        }
+        _ => return None,
+    };
+
+    Some(format!("https://{}/{}", host, account))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deobfuscated(text: &str) -> Vec<String> {
+        ContactMatcher::new(true).find_emails(text)
+    }
+
+    #[test]
+    fn bracketed_at_and_dot_are_deobfuscated() {
+        for text in [
+            "name (at) example (dot) com",
+            "name[at]example[dot]com",
+            "name {at} example {dot} com",
+            "name ( AT ) example ( Dot ) com",
+        ] {
+            assert_eq!(deobfuscated(text), ["name@example.com"], "{}", text);
+        }
+    }
+
+    #[test]
+    fn character_references_are_deobfuscated() {
+        for text in [
+            "name&#64;example&#46;com",
+            "name&#x40;example&#x2E;com",
+            "name&#064;example.com",
+        ] {
+            assert_eq!(deobfuscated(text), ["name@example.com"], "{}", text);
+        }
+    }
+
+    #[test]
+    fn spelled_out_at_needs_a_spelled_out_dot() {
+        assert_eq!(
+            deobfuscated("write to name at mail dot example dot com today"),
+            ["name@mail.example.com"]
+        );
+        assert!(deobfuscated("we met at the office at noon").is_empty());
+        assert!(deobfuscated("look at example.com").is_empty());
+    }
+
+    #[test]
+    fn obfuscations_are_left_alone_by_default() {
+        let matcher = ContactMatcher::new(false);
+        assert!(matcher
+            .find_emails("name (at) example (dot) com")
+            .is_empty());
+        assert!(matcher.find_emails("name at example dot com").is_empty());
+        assert_eq!(
+            matcher.find_emails("name@example.com"),
+            ["name@example.com"]
+        );
+    }
+}
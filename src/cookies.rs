@@ -0,0 +1,110 @@
+/*
+ * Cookie report for --report-cookies.
+ *
+ * Every Set-Cookie header on a crawled page is recorded against the host that sent it, by name
+ * and attributes. Values and expiry dates change from response to response, so they are left out
+ * and a cookie is listed once for as long as its attributes stay the same; a session cookie set
+ * on every page is one entry. Each entry notes the attributes that would be expected and are
+ * missing, and attribute combinations browsers refuse:
+ *
+ *     no Secure              sent over plain http too, where it can be read off the wire
+ *     no HttpOnly            readable by scripts on the page, so by cross-site scripting too
+ *     no SameSite            left to the browser's default, Lax in most current browsers
+ *     SameSite=None without Secure, and __Secure- and __Host- names without the attributes
+ *     their prefix requires, which browsers reject
+ *
+ * Missing HttpOnly is expected of cookies the site's own scripts read, such as consent and CSRF
+ * cookies, so it is a note rather than a finding.
+ */
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cookie {
+    pub name: String,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: Option<String>,
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    // Kept after the browser closes, through Expires or Max-Age
+    pub persistent: bool,
+}
+
+pub fn parse(set_cookie: &str) -> Option<Cookie> {
+    let mut parts = set_cookie.split(';');
+    let (name, _) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let mut cookie = Cookie {
+        name: name.to_string(),
+        secure: false,
+        http_only: false,
+        same_site: None,
+        domain: None,
+        path: None,
+        persistent: false,
+    };
+    for attribute in parts {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "secure" => cookie.secure = true,
+            "httponly" => cookie.http_only = true,
+            "samesite" => cookie.same_site = Some(value.to_ascii_lowercase()),
+            "domain" => cookie.domain = Some(value.trim_start_matches('.').to_ascii_lowercase()),
+            "path" => cookie.path = Some(value.to_string()),
+            "expires" | "max-age" => cookie.persistent = true,
+            _ => {}
+        }
+    }
+    Some(cookie)
+}
+
+impl Cookie {
+    pub fn attributes(&self) -> String {
+        let mut attributes = Vec::new();
+        if let Some(ref domain) = self.domain {
+            attributes.push(format!("Domain={}", domain));
+        }
+        if let Some(ref path) = self.path {
+            attributes.push(format!("Path={}", path));
+        }
+        if self.secure {
+            attributes.push("Secure".to_string());
+        }
+        if self.http_only {
+            attributes.push("HttpOnly".to_string());
+        }
+        if let Some(ref same_site) = self.same_site {
+            attributes.push(format!("SameSite={}", same_site));
+        }
+        if self.persistent {
+            attributes.push("persistent".to_string());
+        }
+        attributes.join("; ")
+    }
+
+    pub fn issues(&self) -> Vec<&'static str> {
+        let mut issues = Vec::new();
+        if !self.secure {
+            issues.push("no Secure");
+        }
+        if !self.http_only {
+            issues.push("no HttpOnly");
+        }
+        match self.same_site.as_deref() {
+            None => issues.push("no SameSite"),
+            Some("none") if !self.secure => issues.push("SameSite=None without Secure"),
+            _ => {}
+        }
+        if self.name.starts_with("__Secure-") && !self.secure {
+            issues.push("__Secure- prefix without Secure");
+        }
+        let host_only = self.secure && self.domain.is_none() && self.path.as_deref() == Some("/");
+        if self.name.starts_with("__Host-") && !host_only {
+            issues.push("__Host- prefix needs Secure, Path=/ and no Domain");
+        }
+        issues
+    }
+}
@@ -0,0 +1,120 @@
+/*
+ * Saved copy of a crawl, written by --dump-dir and read back by --replay.
+ *
+ * Every parsed page is stored as <key>.body with its response headers in <key>.headers, and a
+ * "<key> <status> <url>" line is appended to manifest.txt. Keys are the same hashes the cache
+ * uses. Replaying reads the manifest and serves pages from it instead of the network, so
+ * extraction settings can be changed and results derived again without re-crawling. Pages that
+ * are not in the corpus are skipped during a replay.
+ */
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    StatusCode, Url,
+};
+
+use crate::cache::cache_key;
+
+const MANIFEST: &str = "manifest.txt";
+
+pub struct StoredPage {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+pub struct Corpus {
+    dir: PathBuf,
+    pages: HashMap<Url, (StatusCode, String)>,
+}
+
+impl Corpus {
+    pub fn create(dir: &Path) -> io::Result<Corpus> {
+        fs::create_dir_all(dir)?;
+        Ok(Corpus {
+            dir: dir.to_path_buf(),
+            pages: HashMap::new(),
+        })
+    }
+
+    pub fn open(dir: &Path) -> Result<Corpus, Box<dyn Error>> {
+        let manifest = fs::read_to_string(dir.join(MANIFEST))?;
+        let mut pages = HashMap::new();
+        for (number, line) in manifest.lines().enumerate() {
+            let parts: Vec<&str> = line.splitn(3, ' ').collect();
+            let page = match parts.as_slice() {
+                [key, status, url] => StatusCode::from_str(status)
+                    .ok()
+                    .zip(Url::parse(url).ok())
+                    .map(|(status, url)| (url, (status, key.to_string()))),
+                _ => None,
+            };
+            match page {
+                Some((url, page)) => pages.insert(url, page),
+                None => {
+                    return Err(
+                        format!("Invalid manifest entry on line {}: {}", number + 1, line).into(),
+                    )
+                }
+            };
+        }
+        Ok(Corpus {
+            dir: dir.to_path_buf(),
+            pages,
+        })
+    }
+
+    pub fn store(
+        &self,
+        url: &Url,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> io::Result<()> {
+        let key = cache_key(url);
+        fs::write(self.dir.join(format!("{}.body", key)), body)?;
+        let headers: String = headers
+            .iter()
+            .filter_map(|(name, value)| Some(format!("{}: {}\n", name, value.to_str().ok()?)))
+            .collect();
+        fs::write(self.dir.join(format!("{}.headers", key)), headers)?;
+
+        let mut manifest = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(MANIFEST))?;
+        writeln!(manifest, "{} {} {}", key, status.as_u16(), url)
+    }
+
+    pub fn load(&self, url: &Url) -> io::Result<Option<StoredPage>> {
+        let (status, key) = match self.pages.get(url) {
+            Some(page) => page,
+            None => return Ok(None),
+        };
+        let body = fs::read(self.dir.join(format!("{}.body", key)))?;
+        let mut headers = HeaderMap::new();
+        for line in fs::read_to_string(self.dir.join(format!("{}.headers", key)))?.lines() {
+            if let Some((name, value)) = line.split_once(": ") {
+                if let (Ok(name), Ok(value)) =
+                    (HeaderName::from_str(name), HeaderValue::from_str(value))
+                {
+                    headers.append(name, value);
+                }
+            }
+        }
+        Ok(Some(StoredPage {
+            status: *status,
+            headers,
+            body,
+        }))
+    }
+}
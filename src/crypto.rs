@@ -0,0 +1,260 @@
+/*
+ * Detection of cryptocurrency addresses in page text.
+ *
+ * Candidates are found with loose patterns and then validated where the format allows it:
+ * Bitcoin base58 addresses (P2PKH/P2SH) by their double SHA-256 checksum, bech32/bech32m
+ * addresses by their BCH checksum, mixed-case Ethereum addresses by their EIP-55 checksum, and
+ * Monero addresses by their Keccak checksum. All-lowercase or all-uppercase Ethereum addresses
+ * carry no checksum and are accepted as-is.
+ */
+
+use regex::Regex;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Currency {
+    Bitcoin,
+    Ethereum,
+    Monero,
+}
+
+impl Currency {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Currency::Bitcoin => "bitcoin",
+            Currency::Ethereum => "ethereum",
+            Currency::Monero => "monero",
+        }
+    }
+}
+
+pub struct CryptoMatcher {
+    bitcoin_base58: Regex,
+    bitcoin_bech32: Regex,
+    ethereum: Regex,
+    monero: Regex,
+}
+
+impl CryptoMatcher {
+    pub fn new() -> CryptoMatcher {
+        CryptoMatcher {
+            bitcoin_base58: Regex::new(r"\b[13][1-9A-HJ-NP-Za-km-z]{25,34}\b").unwrap(),
+            bitcoin_bech32: Regex::new(r"\b(?i:bc1[02-9ac-hj-np-z]{11,71})\b").unwrap(),
+            ethereum: Regex::new(r"\b0x[0-9a-fA-F]{40}\b").unwrap(),
+            monero: Regex::new(r"\b[48][1-9A-HJ-NP-Za-km-z]{94}\b").unwrap(),
+        }
+    }
+
+    pub fn find(&self, text: &str) -> Vec<(Currency, String)> {
+        let mut found = Vec::new();
+
+        for candidate in self.bitcoin_base58.find_iter(text) {
+            if valid_base58check(candidate.as_str()) {
+                found.push((Currency::Bitcoin, candidate.as_str().to_string()));
+            }
+        }
+        for candidate in self.bitcoin_bech32.find_iter(text) {
+            if valid_bech32(candidate.as_str()) {
+                found.push((Currency::Bitcoin, candidate.as_str().to_lowercase()));
+            }
+        }
+        for candidate in self.ethereum.find_iter(text) {
+            if valid_eip55(candidate.as_str()) {
+                found.push((Currency::Ethereum, candidate.as_str().to_string()));
+            }
+        }
+        for candidate in self.monero.find_iter(text) {
+            if valid_monero(candidate.as_str()) {
+                found.push((Currency::Monero, candidate.as_str().to_string()));
+            }
+        }
+
+        found
+    }
+}
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn base58_value(c: u8) -> Option<u32> {
+    BASE58_ALPHABET
+        .iter()
+        .position(|&a| a == c)
+        .map(|position| position as u32)
+}
+
+fn decode_base58(input: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in input.bytes() {
+        let mut carry = base58_value(c)?;
+        for byte in bytes.iter_mut().rev() {
+            carry += (*byte as u32) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, (carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    // Each leading '1' encodes a leading zero byte
+    let mut decoded = vec![0; input.bytes().take_while(|&c| c == b'1').count()];
+    decoded.extend(bytes);
+    Some(decoded)
+}
+
+fn valid_base58check(address: &str) -> bool {
+    let decoded = match decode_base58(address) {
+        Some(decoded) if decoded.len() == 25 => decoded,
+        _ => return false,
+    };
+    // Version bytes for mainnet P2PKH and P2SH
+    if decoded[0] != 0x00 && decoded[0] != 0x05 {
+        return false;
+    }
+    let checksum = Sha256::digest(Sha256::digest(&decoded[..21]));
+    checksum[..4] == decoded[21..]
+}
+
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut checksum: u32 = 1;
+    for &value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn valid_bech32(address: &str) -> bool {
+    // Mixed case is explicitly invalid in bech32
+    if address.to_lowercase() != address && address.to_uppercase() != address {
+        return false;
+    }
+    let address = address.to_lowercase();
+    let (hrp, data) = match address.rsplit_once('1') {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    let mut values: Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|c| c & 31));
+    for c in data.bytes() {
+        match BECH32_CHARSET.iter().position(|&a| a == c) {
+            Some(position) => values.push(position as u8),
+            None => return false,
+        }
+    }
+
+    // The constant is 1 for bech32 (segwit v0) and 0x2bc830a3 for bech32m (v1+)
+    let polymod = bech32_polymod(&values);
+    polymod == 1 || polymod == 0x2bc830a3
+}
+
+fn valid_eip55(address: &str) -> bool {
+    let hex = &address[2..];
+    if !hex.chars().any(|c| c.is_ascii_lowercase()) || !hex.chars().any(|c| c.is_ascii_uppercase())
+    {
+        return true;
+    }
+
+    let hash = Keccak256::digest(hex.to_lowercase().as_bytes());
+    hex.chars().enumerate().all(|(i, c)| {
+        let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+        !c.is_ascii_alphabetic() || c.is_ascii_uppercase() == (nibble >= 8)
+    })
+}
+
+// Monero encodes each 8-byte block as 11 characters, with a shorter final block
+const MONERO_ENCODED_BLOCK_SIZES: [usize; 9] = [0, 2, 3, 5, 6, 7, 9, 10, 11];
+
+fn decode_monero_base58(input: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    for block in input.as_bytes().chunks(11) {
+        let size = MONERO_ENCODED_BLOCK_SIZES
+            .iter()
+            .position(|&encoded| encoded == block.len())?;
+        let mut value: u128 = 0;
+        for &c in block {
+            value = value * 58 + base58_value(c)? as u128;
+        }
+        if value >> (size * 8) != 0 {
+            return None;
+        }
+        decoded.extend_from_slice(&value.to_be_bytes()[16 - size..]);
+    }
+    Some(decoded)
+}
+
+fn valid_monero(address: &str) -> bool {
+    let decoded = match decode_monero_base58(address) {
+        Some(decoded) if decoded.len() == 69 => decoded,
+        _ => return false,
+    };
+    let checksum = Keccak256::digest(&decoded[..65]);
+    checksum[..4] == decoded[65..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Swaps the last character for another one of the same alphabet, breaking the checksum
+    fn corrupt(address: &str, replacement: char) -> String {
+        let mut corrupted = address.to_string();
+        corrupted.pop();
+        corrupted.push(replacement);
+        corrupted
+    }
+
+    #[test]
+    fn base58check_addresses() {
+        for address in [
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+            "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+        ] {
+            assert!(valid_base58check(address), "{}", address);
+            assert!(!valid_base58check(&corrupt(address, 'b')), "{}", address);
+        }
+    }
+
+    #[test]
+    fn bech32_addresses() {
+        let address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        assert!(valid_bech32(address));
+        assert!(valid_bech32(&address.to_uppercase()));
+        assert!(!valid_bech32(&corrupt(address, '5')));
+    }
+
+    #[test]
+    fn bech32m_addresses() {
+        let address = "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0";
+        assert!(valid_bech32(address));
+        assert!(!valid_bech32(&corrupt(address, 'q')));
+    }
+
+    #[test]
+    fn eip55_addresses() {
+        let address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        assert!(valid_eip55(address));
+        assert!(!valid_eip55(&address.replacen("aA", "Aa", 1)));
+        // Without mixed case there is no checksum to check
+        assert!(valid_eip55(&address.to_lowercase()));
+    }
+
+    #[test]
+    fn monero_addresses() {
+        let address = "44AFFq5kSiGBoZ4NMDwYtN18obc8AemS33DBLWs3H7otXft3XjrpDtQGv7SqSsaBYBb98uNbr2VBBEt7f2wfn3RVGQBEP3A";
+        assert!(valid_monero(address));
+        assert!(!valid_monero(&corrupt(address, 'B')));
+    }
+}
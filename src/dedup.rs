@@ -0,0 +1,92 @@
+/*
+ * Merging of near-duplicate words.
+ *
+ * Words are visited from most to least frequent, and each one is either kept or folded into an
+ * already kept, more frequent word. Two words are near duplicates when one is a plural of the
+ * other (-s, -es, -ies/-y), or when they are within the threshold number of edits, where an edit
+ * is an insertion, deletion, substitution or swap of two adjacent characters.
+ *
+ * Short words differ from each other by a single edit far too often (cat/car, serve/server), so
+ * edits are only allowed on words with at least MIN_CHARS_PER_EDIT characters for every edit,
+ * and both words must start with the same character. A threshold of 0 merges plurals only.
+ */
+
+use std::collections::HashMap;
+
+const MIN_CHARS_PER_EDIT: usize = 6;
+
+fn is_plural_of(plural: &str, singular: &str) -> bool {
+    match plural.strip_prefix(singular) {
+        Some("s") => !singular.ends_with('s'),
+        Some("es") => ["s", "x", "z", "ch", "sh"]
+            .iter()
+            .any(|ending| singular.ends_with(ending)),
+        _ => plural
+            .strip_suffix("ies")
+            .is_some_and(|stem| singular.strip_suffix('y') == Some(stem) && !stem.is_empty()),
+    }
+}
+
+// Optimal string alignment distance, giving up once it is known to exceed the limit
+fn edit_distance(a: &[char], b: &[char], limit: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > limit {
+        return None;
+    }
+
+    let mut before_previous: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+        if current.iter().min().is_some_and(|&lowest| lowest > limit) {
+            return None;
+        }
+        before_previous = previous;
+        previous = current;
+    }
+
+    Some(previous[b.len()]).filter(|&distance| distance <= limit)
+}
+
+fn is_near(word: &[char], kept: &[char], word_str: &str, kept_str: &str, threshold: usize) -> bool {
+    if is_plural_of(word_str, kept_str) || is_plural_of(kept_str, word_str) {
+        return true;
+    }
+    if threshold == 0 || word.first() != kept.first() {
+        return false;
+    }
+    let limit = threshold.min(word.len().min(kept.len()) / MIN_CHARS_PER_EDIT);
+    limit > 0 && edit_distance(word, kept, limit).is_some()
+}
+
+pub fn merge_near_duplicates(
+    word_count: HashMap<String, u32>,
+    threshold: usize,
+) -> HashMap<String, u32> {
+    let mut words: Vec<(String, u32)> = word_count.into_iter().collect();
+    words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut kept: Vec<(String, Vec<char>, u32)> = Vec::new();
+    for (word, count) in words {
+        let chars: Vec<char> = word.chars().collect();
+        let target = kept.iter_mut().find(|(kept_word, kept_chars, _)| {
+            is_near(&chars, kept_chars, &word, kept_word, threshold)
+        });
+        match target {
+            Some((_, _, kept_count)) => *kept_count += count,
+            None => kept.push((word, chars, count)),
+        }
+    }
+
+    kept.into_iter()
+        .map(|(word, _, count)| (word, count))
+        .collect()
+}
@@ -0,0 +1,133 @@
+/*
+ * Name resolution for --host-override, --resolver and --dns-cache.
+ *
+ * A new client is built for every request, so without a cache every request looks its host up
+ * again. Hosts are instead looked up once, through the system resolver or the name server given
+ * with --resolver, and every client is built with all the addresses found so far pinned to their
+ * hosts. A redirect to a host that has not been looked up yet is resolved by the system. Host
+ * overrides are pinned the same way and are never looked up, which sends the crawl to one
+ * particular backend, say one server behind a load balancer or a staging copy of the site, while
+ * the Host header and TLS names stay those of the real site. Ports always come from the URL.
+ *
+ * Behind --proxy or --tor the proxy connects to the crawled hosts, so they are never looked up
+ * here, which would hand their names to local DNS and fails for .onion names in any case. Only
+ * the proxy's own host is looked up and pinned instead, and host overrides only take effect for
+ * it.
+ */
+
+use std::{
+    collections::HashMap,
+    io,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    sync::Mutex,
+};
+
+use hickory_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig, ResolverOpts},
+    Resolver,
+};
+use reqwest::{blocking::ClientBuilder, Url};
+
+pub struct HostResolver {
+    overrides: HashMap<String, IpAddr>,
+    // None looks hosts up through the system resolver
+    name_server: Option<Resolver>,
+    cache: Option<Mutex<HashMap<String, Vec<SocketAddr>>>>,
+    proxy: Option<Url>,
+}
+
+// HOST:IP, with IPv6 addresses optionally in brackets
+pub fn parse_override(host_override: &str) -> Result<(String, IpAddr), String> {
+    let (host, ip) = host_override
+        .split_once(':')
+        .ok_or_else(|| "expected HOST:IP".to_string())?;
+    let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+    if host.is_empty() {
+        return Err("expected HOST:IP".to_string());
+    }
+    let ip = ip.trim().trim_start_matches('[').trim_end_matches(']');
+    let ip = ip
+        .parse()
+        .map_err(|_| format!("'{}' is not an IP address", ip))?;
+    Ok((host, ip))
+}
+
+// An IP address, or an address and port when the name server does not listen on 53
+pub fn parse_name_server(name_server: &str) -> Result<SocketAddr, String> {
+    let name_server = name_server.trim();
+    name_server
+        .parse()
+        .or_else(|_| {
+            name_server
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse()
+                .map(|ip| SocketAddr::new(ip, 53))
+        })
+        .map_err(|_| format!("'{}' is not an IP address", name_server))
+}
+
+impl HostResolver {
+    pub fn new(
+        overrides: Vec<(String, IpAddr)>,
+        name_server: Option<SocketAddr>,
+        cache: bool,
+        proxy: Option<Url>,
+    ) -> io::Result<HostResolver> {
+        let name_server = match name_server {
+            Some(addr) => {
+                let name_servers =
+                    NameServerConfigGroup::from_ips_clear(&[addr.ip()], addr.port(), true);
+                let config = ResolverConfig::from_parts(None, Vec::new(), name_servers);
+                Some(Resolver::new(config, ResolverOpts::default())?)
+            }
+            None => None,
+        };
+        // Looking up through a name server of our own has to pin the result, so it is cached too
+        let cache = (cache || name_server.is_some()).then(|| Mutex::new(HashMap::new()));
+        Ok(HostResolver {
+            overrides: overrides.into_iter().collect(),
+            name_server,
+            cache,
+            proxy,
+        })
+    }
+
+    fn lookup(&self, host: &str) -> io::Result<Vec<SocketAddr>> {
+        match self.name_server {
+            Some(ref resolver) => resolver
+                .lookup_ip(host)
+                .map(|ips| ips.iter().map(|ip| SocketAddr::new(ip, 0)).collect())
+                .map_err(io::Error::from),
+            None => (host, 0).to_socket_addrs().map(Iterator::collect),
+        }
+    }
+
+    pub fn pin(&self, mut builder: ClientBuilder, url: &Url) -> io::Result<ClientBuilder> {
+        for (host, ip) in &self.overrides {
+            builder = builder.resolve(host, SocketAddr::new(*ip, 0));
+        }
+        let Some(ref cache) = self.cache else {
+            return Ok(builder);
+        };
+        let mut cache = cache.lock().unwrap_or_else(|err| err.into_inner());
+        // IP addresses are left to reqwest, only names are looked up
+        let host = match self.proxy {
+            Some(ref proxy) => proxy.domain(),
+            None => url.domain(),
+        };
+        if let Some(host) = host {
+            let host = host.trim_end_matches('.').to_ascii_lowercase();
+            if !self.overrides.contains_key(&host) && !cache.contains_key(&host) {
+                let addrs = self.lookup(&host).map_err(|err| {
+                    io::Error::new(err.kind(), format!("Unable to resolve '{}': {}", host, err))
+                })?;
+                cache.insert(host, addrs);
+            }
+        }
+        for (host, addrs) in cache.iter() {
+            builder = builder.resolve_to_addrs(host, addrs);
+        }
+        Ok(builder)
+    }
+}
@@ -0,0 +1,120 @@
+/*
+ * RSS, Atom and JSON Feed support for --feeds.
+ *
+ * Feeds are found through the <link rel="alternate"> elements pages advertise them with and are
+ * followed like any other link, so they count as a hop and show up in the links output. A fetched
+ * feed is parsed with feed-rs and handed to the rest of the crawl as a page holding the feed title
+ * and description and, for every entry, its title, summary, content and authors. Author emails
+ * end up in that text, where the email extraction finds them. Entries linking to a page that was
+ * already crawled are left out, so article text is not counted twice. Entry permalinks are not
+ * followed, but links inside HTML entry content are, like links on any page.
+ */
+
+use feed_rs::model::{Person, Text};
+use html_escape::encode_text;
+use reqwest::{
+    header::{HeaderMap, CONTENT_TYPE},
+    Url,
+};
+use select::{document::Document, predicate::Name};
+
+const FEED_TYPES: &[&str] = &[
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/feed+json",
+];
+
+pub fn feed_links(document: &Document, page_url: &Url) -> Vec<Url> {
+    document
+        .find(Name("link"))
+        .filter(|node| {
+            node.attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("alternate"))
+            }) && node.attr("type").is_some_and(|content_type| {
+                FEED_TYPES.contains(&content_type.trim().to_ascii_lowercase().as_str())
+            })
+        })
+        .filter_map(|node| node.attr("href"))
+        .filter_map(|href| page_url.join(href.trim()).ok())
+        .collect()
+}
+
+pub fn is_feed(headers: &HeaderMap, body: &[u8]) -> bool {
+    let declared = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| {
+            let content_type = content_type.to_ascii_lowercase();
+            FEED_TYPES.iter().any(|feed| content_type.starts_with(feed))
+        });
+    // Many servers send feeds as text/xml, so the root element decides for those
+    let start = String::from_utf8_lossy(&body[..body.len().min(512)]).to_ascii_lowercase();
+    declared || start.contains("<rss") || start.contains("<feed") || start.contains("<rdf:rdf")
+}
+
+// HTML text is kept as it is, so its markup is parsed rather than counted as words
+fn text(text: &Text) -> String {
+    if text.content_type.to_string().contains("html") {
+        text.content.clone()
+    } else {
+        encode_text(&text.content).into_owned()
+    }
+}
+
+fn authors(people: &[Person]) -> String {
+    people
+        .iter()
+        .flat_map(|person| [person.name.as_deref(), person.email.as_deref()])
+        .flatten()
+        .map(|part| encode_text(part).into_owned())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+pub fn as_html(
+    body: &[u8],
+    base_url: &Url,
+    crawled: impl Fn(&Url) -> bool,
+) -> Result<String, String> {
+    let feed = feed_rs::parser::parse(body).map_err(|err| err.to_string())?;
+    let mut html = String::from("<html><body>");
+    if let Some(ref title) = feed.title {
+        html.push_str(&format!("<h1>{}</h1>", text(title)));
+    }
+    if let Some(ref description) = feed.description {
+        html.push_str(&format!("<p>{}</p>", text(description)));
+    }
+    html.push_str(&format!("<p>{}</p>", authors(&feed.authors)));
+    for entry in &feed.entries {
+        let seen = entry
+            .links
+            .iter()
+            .filter_map(|link| base_url.join(&link.href).ok())
+            .any(|link| crawled(&link));
+        if seen {
+            continue;
+        }
+        html.push_str("<article>");
+        if let Some(ref title) = entry.title {
+            html.push_str(&format!("<h2>{}</h2>", text(title)));
+        }
+        if let Some(ref summary) = entry.summary {
+            html.push_str(&format!("<p>{}</p>", text(summary)));
+        }
+        if let Some(ref content) = entry.content {
+            // Plain text needs a paragraph of its own to be read, markup brings its own elements
+            if let Some(ref body) = content.body {
+                if content.content_type.to_string().contains("html") {
+                    html.push_str(&format!("<div>{}</div>", body));
+                } else {
+                    html.push_str(&format!("<p>{}</p>", encode_text(body)));
+                }
+            }
+        }
+        html.push_str(&format!("<p>{}</p>", authors(&entry.authors)));
+        html.push_str("</article>");
+    }
+    html.push_str("</body></html>");
+    Ok(html)
+}
@@ -0,0 +1,109 @@
+/*
+ * Security header report for --report-headers.
+ *
+ * The response headers of every crawled page are summarised per host: the distinct values seen
+ * for each header in HEADERS, and how many pages went without each security header. Headers that
+ * are missing from some or all of a host's pages are flagged:
+ *
+ *     Content-Security-Policy    restricts where scripts and other content may be loaded from
+ *     Strict-Transport-Security  keeps browsers on https; only counted on pages served over https,
+ *                                browsers ignore it over plain http
+ *     X-Frame-Options            stops other sites framing the page; not needed when the
+ *                                Content-Security-Policy has a frame-ancestors directive
+ *     X-Content-Type-Options     stops browsers guessing content types
+ *     Referrer-Policy            limits the URL sent on to other sites
+ *
+ * Permissions-Policy is listed when present but not expected. Server, X-Powered-By and
+ * X-AspNet-Version are listed too, and flagged when they give away a version number.
+ */
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use reqwest::{header::HeaderMap, Url};
+
+// Names as they are usually written, and whether pages are expected to send them
+const HEADERS: &[(&str, bool)] = &[
+    ("Server", false),
+    ("X-Powered-By", false),
+    ("X-AspNet-Version", false),
+    ("Content-Security-Policy", true),
+    ("Strict-Transport-Security", true),
+    ("X-Frame-Options", true),
+    ("X-Content-Type-Options", true),
+    ("Referrer-Policy", true),
+    ("Permissions-Policy", false),
+];
+
+const DISCLOSING: &[&str] = &["Server", "X-Powered-By", "X-AspNet-Version"];
+
+#[derive(Default)]
+pub struct HostHeaders {
+    pub pages: usize,
+    https_pages: usize,
+    pub values: BTreeMap<&'static str, BTreeSet<String>>,
+    // Pages each expected header was missing from
+    pub missing: BTreeMap<&'static str, usize>,
+}
+
+impl HostHeaders {
+    pub fn record(&mut self, url: &Url, headers: &HeaderMap) {
+        self.pages += 1;
+        let https = url.scheme() == "https";
+        if https {
+            self.https_pages += 1;
+        }
+        let frame_ancestors = headers
+            .get_all("content-security-policy")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(|policy| policy.to_ascii_lowercase().contains("frame-ancestors"));
+        for &(name, expected) in HEADERS {
+            let values: Vec<String> = headers
+                .get_all(name)
+                .iter()
+                .map(|value| String::from_utf8_lossy(value.as_bytes()).trim().to_string())
+                .collect();
+            let applies = match name {
+                "Strict-Transport-Security" => https,
+                "X-Frame-Options" => !frame_ancestors,
+                _ => true,
+            };
+            if values.is_empty() && expected && applies {
+                *self.missing.entry(name).or_insert(0) += 1;
+            }
+            if !values.is_empty() {
+                self.values.entry(name).or_default().extend(values);
+            }
+        }
+    }
+
+    pub fn issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        for (name, &missing) in &self.missing {
+            let pages = match *name {
+                "Strict-Transport-Security" => self.https_pages,
+                _ => self.pages,
+            };
+            if missing == pages {
+                issues.push(format!("No {}", name));
+            } else {
+                issues.push(format!("No {} on {} of {} pages", name, missing, pages));
+            }
+        }
+        if self.https_pages == 0 {
+            issues.push("Not served over https".to_string());
+        }
+        for name in DISCLOSING {
+            let versions = self
+                .values
+                .get(name)
+                .into_iter()
+                .flatten()
+                .filter(|value| value.chars().any(|c| c.is_ascii_digit()));
+            for value in versions {
+                issues.push(format!("{} gives away a version: {}", name, value));
+            }
+        }
+        issues
+    }
+}
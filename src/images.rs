@@ -0,0 +1,106 @@
+/*
+ * Image URLs and their EXIF metadata, used by --images and --image-exif.
+ *
+ * Images are taken from the src and srcset attributes of <img> elements and from the srcset of
+ * <source> elements inside <picture>. Every srcset candidate is recorded, not only the one a
+ * browser would pick.
+ *
+ * Metadata is only read from formats that carry EXIF: JPEG, TIFF, HEIF, PNG and WebP. URLs with
+ * other well-known image extensions (GIF, SVG, ICO, BMP, AVIF) are not downloaded at all, and
+ * responses that do not declare an image content type are dropped before their body is read.
+ * Each field is reported with its display value, except the GPS position, which is converted
+ * from degrees, minutes and seconds to signed decimal degrees.
+ */
+
+use std::io::Cursor;
+
+use exif::{In, Reader, Tag, Value};
+use reqwest::Url;
+use select::{
+    document::Document,
+    predicate::{Name, Predicate},
+};
+
+pub type ExifFields = Vec<(&'static str, String)>;
+
+const SKIPPED_EXTENSIONS: &[&str] = &[".gif", ".svg", ".ico", ".bmp", ".avif"];
+
+const TEXT_FIELDS: &[(&str, Tag)] = &[
+    ("make", Tag::Make),
+    ("model", Tag::Model),
+    ("software", Tag::Software),
+    ("artist", Tag::Artist),
+    ("copyright", Tag::Copyright),
+    ("taken", Tag::DateTimeOriginal),
+    ("description", Tag::ImageDescription),
+];
+
+pub fn image_urls(document: &Document, page_url: &Url) -> Vec<Url> {
+    let mut links = Vec::new();
+    for node in document.find(Name("img").or(Name("source"))) {
+        if node.name() == Some("img") {
+            links.extend(node.attr("src"));
+        }
+        if let Some(srcset) = node.attr("srcset") {
+            // Candidates are "url [descriptor]" separated by commas
+            links.extend(
+                srcset
+                    .split(',')
+                    .filter_map(|candidate| candidate.split_whitespace().next()),
+            );
+        }
+    }
+    links
+        .into_iter()
+        .map(str::trim)
+        .filter(|link| !link.is_empty() && !link.starts_with("data:"))
+        .filter_map(|link| page_url.join(link).ok())
+        .collect()
+}
+
+pub fn may_have_exif(url: &Url) -> bool {
+    let path = url.path().to_lowercase();
+    !SKIPPED_EXTENSIONS
+        .iter()
+        .any(|extension| path.ends_with(extension))
+}
+
+// Decimal degrees from a degrees, minutes, seconds triple and its N/S or E/W reference
+fn gps_coordinate(exif: &exif::Exif, value_tag: Tag, ref_tag: Tag) -> Option<f64> {
+    let parts = match exif.get_field(value_tag, In::PRIMARY)?.value {
+        Value::Rational(ref parts) if parts.len() >= 3 => parts.clone(),
+        _ => return None,
+    };
+    let degrees = parts[0].to_f64() + parts[1].to_f64() / 60.0 + parts[2].to_f64() / 3600.0;
+    let reference = exif
+        .get_field(ref_tag, In::PRIMARY)?
+        .display_value()
+        .to_string();
+    if reference.contains('S') || reference.contains('W') {
+        Some(-degrees)
+    } else {
+        Some(degrees)
+    }
+}
+
+pub fn read_exif(body: &[u8]) -> Option<ExifFields> {
+    let exif = Reader::new()
+        .read_from_container(&mut Cursor::new(body))
+        .ok()?;
+    let mut fields = Vec::new();
+    for &(name, tag) in TEXT_FIELDS {
+        if let Some(field) = exif.get_field(tag, In::PRIMARY) {
+            let value = field.display_value().to_string();
+            let value = value.trim_matches('"').trim();
+            if !value.is_empty() {
+                fields.push((name, value.to_string()));
+            }
+        }
+    }
+    let latitude = gps_coordinate(&exif, Tag::GPSLatitude, Tag::GPSLatitudeRef);
+    let longitude = gps_coordinate(&exif, Tag::GPSLongitude, Tag::GPSLongitudeRef);
+    if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+        fields.push(("gps", format!("{:.6},{:.6}", latitude, longitude)));
+    }
+    Some(fields)
+}
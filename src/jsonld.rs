@@ -0,0 +1,95 @@
+/*
+ * Extraction of contact details from JSON-LD structured data.
+ *
+ * Every <script type="application/ld+json"> block is parsed and walked recursively, so arrays,
+ * @graph containers and nested objects (contactPoint, author, publisher...) are all covered.
+ * Names are only taken from objects typed as a person or organisation, since "name" on other
+ * types is usually a page or product title. Blocks that are not valid JSON are skipped.
+ */
+
+use select::{document::Document, predicate::Name};
+use serde_json::Value;
+
+use crate::contacts::canonical_email;
+
+const ENTITY_TYPES: &[&str] = &[
+    "Person",
+    "Organization",
+    "Corporation",
+    "LocalBusiness",
+    "NGO",
+    "EducationalOrganization",
+    "GovernmentOrganization",
+    "NewsMediaOrganization",
+];
+
+#[derive(Default)]
+pub struct Findings {
+    pub names: Vec<String>,
+    pub emails: Vec<String>,
+    pub phones: Vec<String>,
+    pub same_as: Vec<String>,
+}
+
+pub fn blocks(document: &Document) -> Vec<Value> {
+    document
+        .find(Name("script"))
+        .filter(|script| {
+            script
+                .attr("type")
+                .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("application/ld+json"))
+        })
+        .filter_map(|script| serde_json::from_str(&script.text()).ok())
+        .collect()
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(text) => vec![text.trim().to_string()],
+        Value::Array(values) => values.iter().flat_map(strings).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn is_entity(object: &serde_json::Map<String, Value>) -> bool {
+    object
+        .get("@type")
+        .map(strings)
+        .unwrap_or_default()
+        .iter()
+        .any(|kind| ENTITY_TYPES.contains(&kind.as_str()))
+}
+
+pub fn extract(value: &Value, findings: &mut Findings) {
+    match value {
+        Value::Object(object) => {
+            if is_entity(object) {
+                if let Some(name) = object.get("name") {
+                    findings.names.extend(strings(name));
+                }
+            }
+            if let Some(email) = object.get("email") {
+                findings.emails.extend(
+                    strings(email)
+                        .into_iter()
+                        .map(|email| canonical_email(email.trim_start_matches("mailto:"))),
+                );
+            }
+            if let Some(telephone) = object.get("telephone") {
+                findings.phones.extend(strings(telephone));
+            }
+            if let Some(same_as) = object.get("sameAs") {
+                findings.same_as.extend(strings(same_as));
+            }
+            for nested in object.values() {
+                extract(nested, findings);
+            }
+        }
+        Value::Array(values) => {
+            for nested in values {
+                extract(nested, findings);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * Session setup by submitting a login form before crawling.
+ *
+ * The login page is fetched and the first form with a password field is filled in: fields the
+ * form already carries (hidden CSRF tokens, default values) are kept and the given fields
+ * override or add to them. The form is submitted once with a client sharing the crawl's cookie
+ * jar, so the session cookies it sets are sent with every later request. If the page returned
+ * after submitting still has a password field, the login is taken to have failed.
+ *
+ * Both requests follow the crawl's retry policy: when the server asks to wait with Retry-After,
+ * up to MAX_HOLD, the request is sent once more after the wait. A POST submission is only
+ * repeated when the policy allows retrying non-idempotent requests.
+ */
+
+use std::{error::Error, thread};
+
+use reqwest::{
+    blocking::{Client, RequestBuilder, Response},
+    Url,
+};
+use select::{document::Document, node::Node, predicate::Name};
+
+use crate::throttle::{RetryPolicy, MAX_HOLD};
+
+// Path fragments of links that would end the session if they were crawled
+const LOGOUT_PATTERNS: &[&str] = &[
+    "logout", "log-out", "log_out", "signout", "sign-out", "sign_out", "logoff",
+];
+
+fn input_type(input: &Node) -> String {
+    input
+        .attr("type")
+        .unwrap_or("text")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn has_password_field(node: &Node) -> bool {
+    node.find(Name("input"))
+        .any(|input| input_type(&input) == "password")
+}
+
+fn default_fields(form: &Node) -> Vec<(String, String)> {
+    form.find(Name("input"))
+        .filter(|input| match input_type(input).as_str() {
+            "submit" | "button" | "image" | "reset" | "file" => false,
+            "checkbox" | "radio" => input.attr("checked").is_some(),
+            _ => true,
+        })
+        .filter_map(|input| {
+            let name = input.attr("name")?;
+            Some((
+                name.to_string(),
+                input.attr("value").unwrap_or("").to_string(),
+            ))
+        })
+        .collect()
+}
+
+fn send(client: &Client, request: RequestBuilder, retry: RetryPolicy) -> reqwest::Result<Response> {
+    let request = request.build()?;
+    let again = request.try_clone();
+    let method = request.method().clone();
+    let response = client.execute(request)?;
+    match (
+        again,
+        retry.retry_delay(&method, response.status(), response.headers()),
+    ) {
+        (Some(again), Some(pause)) if pause <= MAX_HOLD => {
+            thread::sleep(pause);
+            client.execute(again)
+        }
+        _ => Ok(response),
+    }
+}
+
+pub fn submit(
+    client: &Client,
+    login_url: &Url,
+    fields: &[(String, String)],
+    retry: RetryPolicy,
+) -> Result<(), Box<dyn Error>> {
+    let page = send(client, client.get(login_url.as_str()), retry)?.error_for_status()?;
+    let document = Document::from(page.text()?.as_str());
+    let form = document
+        .find(Name("form"))
+        .find(has_password_field)
+        .ok_or("no form with a password field on the login page")?;
+
+    let action = match form.attr("action").map(str::trim) {
+        Some(action) if !action.is_empty() => login_url.join(action)?,
+        _ => login_url.clone(),
+    };
+    let mut values = default_fields(&form);
+    for (name, value) in fields {
+        match values.iter_mut().find(|(existing, _)| existing == name) {
+            Some(existing) => existing.1 = value.clone(),
+            None => values.push((name.clone(), value.clone())),
+        }
+    }
+
+    let method = form.attr("method").unwrap_or("get").trim();
+    let request = if method.eq_ignore_ascii_case("post") {
+        client.post(action.as_str()).form(&values)
+    } else {
+        client.get(action.as_str()).query(&values)
+    };
+    let response = send(client, request, retry)?.error_for_status()?;
+    let document = Document::from(response.text()?.as_str());
+    if document
+        .find(Name("form"))
+        .any(|form| has_password_field(&form))
+    {
+        return Err("the response still contains a login form".into());
+    }
+    Ok(())
+}
+
+pub fn is_logout(url: &Url) -> bool {
+    let path = url.path().to_ascii_lowercase();
+    LOGOUT_PATTERNS.iter().any(|pattern| path.contains(pattern))
+}
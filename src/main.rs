@@ -28,12 +28,50 @@
  *
  */
 
+mod benchmark;
+mod cache;
+mod contacts;
+mod cookies;
+mod corpus;
+mod crypto;
+mod dedup;
+mod dns;
+mod feeds;
+mod headers;
+mod images;
+mod jsonld;
+mod login;
+mod mangle;
+mod mx;
+mod pdf;
+mod prominence;
+#[cfg(feature = "render")]
+mod render;
+mod report;
+mod sensitive;
+mod simhash;
+mod sketch;
+mod sqlite;
+mod subdomains;
+mod tech;
+mod tfidf;
+mod throttle;
+mod tokenizer;
+#[cfg(feature = "tui")]
+mod tui;
+mod wordcloud;
+
 use std::{
+    borrow::Cow,
+    cmp::Ordering,
     collections::{HashMap, HashSet},
-    fs::File,
-    io::{BufRead, BufReader, Write},
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    net::IpAddr,
     path::Path,
     str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use select::{
@@ -43,13 +81,53 @@ use select::{
 };
 
 use reqwest::{
-    header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT},
-    Url,
+    cookie::Jar,
+    header::{
+        HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, SET_COOKIE,
+        STRICT_TRANSPORT_SECURITY, USER_AGENT,
+    },
+    tls, Certificate, Method, Proxy, StatusCode, Url,
 };
 
-use clap::Parser;
+use benchmark::{Phase, Timings};
+use cache::Cache;
+use clap::{Parser, ValueEnum};
+use contacts::{plausible_email, social_account, ContactMatcher};
+use corpus::{Corpus, StoredPage};
+use crypto::{CryptoMatcher, Currency};
+use flate2::{write::GzEncoder, Compression};
+use images::ExifFields;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use regex::Regex;
-use unicode_normalization::UnicodeNormalization;
+use serde_json::json;
+use sketch::ApproxCounter;
+use subdomains::SubdomainMatcher;
+use tech::Signature;
+use throttle::{BlockAction, HostThrottle, RetryPolicy, TokenBucket};
+use tokenizer::{Apostrophes, Contractions, NormalizationForm, TextChunks, Tokenizer};
+
+const TOR_PROXY: &str = "socks5h://127.0.0.1:9050";
+
+// Suffixes that only internal DNS servers answer for, along with single label names
+const INTERNAL_SUFFIXES: &[&str] = &[
+    "corp",
+    "home",
+    "internal",
+    "intranet",
+    "lan",
+    "local",
+    "localdomain",
+    "private",
+];
+
+// Process exit codes, also listed in the --help text. Every entry in the error list counts as a
+// page error: requests that failed, 4xx and 5xx responses and redirects out of --strict-scope.
+// When the seed itself answers with an error status or is redirected out of scope, nothing was
+// crawled, which is a failed seed rather than a page error.
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_PAGE_ERRORS: i32 = 1;
+const EXIT_CONFIG_ERROR: i32 = 2;
+const EXIT_SEED_FAILED: i32 = 3;
 
 struct Or(Vec<Box<dyn Predicate>>);
 
@@ -66,6 +144,153 @@ struct CrawlConfig {
     min_length: usize,
     user_agent: Option<String>,
     headers: HeaderMap,
+    parse_status: Vec<u16>,
+    preflight: bool,
+    max_body_size: Option<u64>,
+    cache: Option<Cache>,
+    by_page: bool,
+    crypto: Option<CryptoMatcher>,
+    respect_meta_robots: bool,
+    min_tls: Option<tls::Version>,
+    find_emails: bool,
+    find_socials: bool,
+    find_phones: bool,
+    contacts: ContactMatcher,
+    tech: Option<Vec<Signature>>,
+    sensitive: Option<Vec<sensitive::Pattern>>,
+    find_forms: bool,
+    follow_forms: bool,
+    find_params: bool,
+    report_cookies: bool,
+    report_headers: bool,
+    backoff_on_block: bool,
+    graph: bool,
+    graph_offsite: bool,
+    tokenizer: Tokenizer,
+    strip_params: Vec<String>,
+    jsonld: bool,
+    jsonld_raw: bool,
+    delay: Duration,
+    jitter: Duration,
+    rotate_agent: bool,
+    decode_entities: bool,
+    percent_decode: bool,
+    cookies: Option<Arc<Jar>>,
+    fetcher: Box<dyn Fetcher>,
+    prominence: bool,
+    offsite_depth: Option<u32>,
+    seed_domain: Option<String>,
+    count_words: bool,
+    document_frequency: bool,
+    meta_weight: Option<u32>,
+    http1_only: bool,
+    exclude_hidden: bool,
+    data_attrs: Vec<String>,
+    dump: Option<Corpus>,
+    track_word_pages: bool,
+    word_sources: Option<usize>,
+    proxy: Option<Proxy>,
+    header_templates: Vec<(HeaderName, String)>,
+    seed_host: String,
+    canonical_dedup: bool,
+    keep_pages: bool,
+    retry: RetryPolicy,
+    max_pagination: Option<u32>,
+    fuzzy_host: bool,
+    treat_hashbang_as_page: bool,
+    subdomains: Option<SubdomainMatcher>,
+    find_images: bool,
+    parse_pdf: bool,
+    skip_similar: Option<u32>,
+    offsite_once: bool,
+    accept_hostname_mismatch: bool,
+    feeds: bool,
+    strict_scope: bool,
+    prefer_https: bool,
+    by_depth: bool,
+    min_content_length: Option<u64>,
+    max_links_per_page: Option<usize>,
+    ca_certs: Vec<Certificate>,
+    resolver: Option<dns::HostResolver>,
+}
+
+struct PageResult {
+    url: Url,
+    words: HashMap<String, u32>,
+}
+
+#[derive(Default)]
+struct DepthWords {
+    pages: usize,
+    words: HashMap<String, u32>,
+}
+
+struct CrawlError {
+    url: Url,
+    depth: u32,
+    kind: &'static str,
+    message: String,
+}
+
+// Only collected when crawling offsite, for the per-host breakdown
+#[derive(Default)]
+struct HostSummary {
+    parsed: usize,
+    words: HashSet<String>,
+}
+
+struct CrawlState {
+    visited_urls: HashSet<Url>,
+    links: Vec<(Url, u16)>,
+    pages: Vec<PageResult>,
+    crypto: HashMap<Currency, HashSet<String>>,
+    parsed_pages: usize,
+    emails: HashMap<String, HashSet<Url>>,
+    socials: HashMap<String, HashSet<Url>>,
+    technologies: HashMap<String, String>,
+    // Flagged links with the category of the pattern they matched
+    sensitive: HashMap<Url, String>,
+    forms: HashMap<(Url, String), HashSet<String>>,
+    params: HashSet<String>,
+    // Host that set each cookie, only filled in with --report-cookies
+    cookies: HashSet<(String, cookies::Cookie)>,
+    // Only filled in with --report-headers
+    header_report: HashMap<String, headers::HostHeaders>,
+    throttles: HashMap<String, HostThrottle>,
+    edges: HashSet<(Url, Url)>,
+    names: HashMap<String, HashSet<Url>>,
+    phones: HashMap<String, HashSet<Url>>,
+    jsonld_blocks: Vec<(Url, serde_json::Value)>,
+    scores: HashMap<String, f64>,
+    hosts: HashMap<String, HostSummary>,
+    errors: Vec<CrawlError>,
+    word_pages: HashMap<String, u32>,
+    // Pages each word was found on, up to --word-sources of them
+    word_sources: HashMap<String, Vec<Url>>,
+    canonicals: HashSet<Url>,
+    // Number of pagination links followed to reach a page
+    pagination: HashMap<Url, u32>,
+    subdomains: HashSet<String>,
+    rate_limit: Option<TokenBucket>,
+    images: HashMap<String, HashSet<Url>>,
+    seen_pages: simhash::SeenPages,
+    // Pages skipped by --skip-similar, with the page they were found to resemble
+    similar_to: HashMap<Url, Url>,
+    approx: Option<ApproxCounter>,
+    // Hosts that sent Strict-Transport-Security, and whether it includes their subdomains
+    hsts_hosts: HashMap<String, bool>,
+    // Hosts and ports that failed over https with --prefer-https
+    plain_http_hosts: HashSet<String>,
+    // Indexed by depth, only filled in with --by-depth
+    depths: Vec<DepthWords>,
+    shuffle: Option<StdRng>,
+    // The page read with --stdin and the URL it stands for, until it has been extracted from
+    stdin_page: Option<(Url, Vec<u8>)>,
+    timings: Option<Timings>,
+    // When parsing and extraction of the page being crawled started or resumed, for --benchmark
+    parse_started: Option<Instant>,
+    #[cfg(feature = "tui")]
+    dashboard: Option<tui::Dashboard>,
 }
 
 fn headers_from_strings(headers: &[String]) -> Result<HeaderMap, Box<dyn std::error::Error>> {
@@ -85,243 +310,4038 @@ fn headers_from_strings(headers: &[String]) -> Result<HeaderMap, Box<dyn std::er
     Ok(header_map)
 }
 
-fn process_node(
-    node: &Node,
-    base_url: &Url,
-    depth: u32,
-    word_count: &mut HashMap<String, u32>,
-    visited_urls: &mut HashSet<Url>,
-    config: &CrawlConfig,
-) {
-    if depth <= config.max_depth {
-        let link = node.attr("href").and_then(|href| base_url.join(href).ok());
-
-        if let Some(url) = link {
-            // Only follow the link if follow_offsite is true or if the domains match
-            if config.follow_offsite || url.domain() == base_url.domain() {
-                if let Ok(new_word_count) =
-                    unique_words_from_url_recursive(&url, depth + 1, visited_urls, config)
-                {
-                    for (word, count) in new_word_count {
-                        *word_count.entry(word).or_insert(0) += count;
-                    }
-                }
+const TEMPLATE_TOKENS: &[&str] = &["random", "uuid", "seed-host"];
+
+fn header_templates_from_strings(
+    templates: &[String],
+) -> Result<Vec<(HeaderName, String)>, Box<dyn std::error::Error>> {
+    let token = Regex::new(r"\{\{([^{}]*)\}\}").unwrap();
+    let mut header_templates = Vec::new();
+    for template in templates {
+        let (name, value) = template
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid header template format: {}", template))?;
+        for captures in token.captures_iter(value) {
+            if !TEMPLATE_TOKENS.contains(&&captures[1]) {
+                return Err(
+                    format!("Unknown template token {} in: {}", &captures[0], template).into(),
+                );
             }
         }
+        HeaderValue::from_str(&render_template(value.trim(), ""))?;
+        header_templates.push((HeaderName::from_str(name.trim())?, value.trim().to_string()));
     }
+    Ok(header_templates)
 }
 
-fn unique_words_from_url_recursive(
+fn render_template(template: &str, seed_host: &str) -> String {
+    let mut rng = rand::thread_rng();
+    let mut value = template.replace("{{seed-host}}", seed_host);
+    while value.contains("{{random}}") {
+        value = value.replacen("{{random}}", &format!("{:016x}", rng.gen::<u64>()), 1);
+    }
+    while value.contains("{{uuid}}") {
+        // Version 4 and RFC 4122 variant bits on top of random ones
+        let bits =
+            (rng.gen::<u128>() & !(0xf000 << 64) & !(0xc << 60)) | (0x4000 << 64) | (0x8 << 60);
+        let hex = format!("{:032x}", bits);
+        let uuid = format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        );
+        value = value.replacen("{{uuid}}", &uuid, 1);
+    }
+    value
+}
+
+fn should_parse_status(status: StatusCode, parse_status: &[u16]) -> bool {
+    // With no explicit list, only successful (2xx) responses are extracted
+    if parse_status.is_empty() {
+        status.is_success()
+    } else {
+        parse_status.contains(&status.as_u16())
+    }
+}
+
+fn is_html_content(headers: &HeaderMap) -> bool {
+    // A missing Content-Type is given the benefit of the doubt
+    match headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(content_type) => {
+            let content_type = content_type.to_ascii_lowercase();
+            content_type.starts_with("text/")
+                || content_type.contains("html")
+                || content_type.contains("xml")
+        }
+        None => true,
+    }
+}
+
+fn exceeds_body_limit(headers: &HeaderMap, max_body_size: Option<u64>) -> bool {
+    let content_length = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    match (max_body_size, content_length) {
+        (Some(max), Some(length)) => length > max,
+        _ => false,
+    }
+}
+
+fn preflight_status(
+    client: &reqwest::blocking::Client,
     url: &Url,
-    depth: u32,
-    visited_urls: &mut HashSet<Url>,
     config: &CrawlConfig,
-) -> Result<HashMap<String, u32>, Box<dyn std::error::Error>> {
-    if !visited_urls.insert(url.clone()) {
-        // If the URL is already in the visited set, return an empty HashMap
-        return Ok(HashMap::new());
+) -> Result<Option<StatusCode>, Box<dyn std::error::Error>> {
+    let resp = client.head(url.as_str()).send()?;
+    let status = resp.status();
+    // Servers that refuse HEAD are simply fetched with a GET instead
+    if status == StatusCode::METHOD_NOT_ALLOWED {
+        return Ok(None);
     }
-    let mut req_headers = HeaderMap::new();
-    if let Some(ref agent) = config.user_agent {
-        req_headers.insert(USER_AGENT, HeaderValue::from_str(agent)?);
+    let parsed = is_html_content(resp.headers())
+        || (config.parse_pdf && pdf::is_pdf_content(resp.headers()));
+    if !parsed || exceeds_body_limit(resp.headers(), config.max_body_size) {
+        return Ok(Some(status));
     }
+    Ok(None)
+}
 
-    let client = reqwest::blocking::Client::builder()
-        .default_headers(config.headers.clone())
-        .build()?;
+// Summaries counted with --meta-words, as name or property attribute values
+const SUMMARY_META: &[&str] = &["description", "og:title", "og:description"];
 
-    let resp = client.get(url.as_str()).send()?;
+#[derive(Default)]
+struct PageMeta {
+    noindex: bool,
+    nofollow: bool,
+    summaries: Vec<String>,
+}
 
-    let document = Document::from_read(resp)?;
+fn page_meta(document: &Document, config: &CrawlConfig) -> PageMeta {
+    let mut page_meta = PageMeta::default();
+    for meta in document.find(Name("meta")) {
+        let name = meta
+            .attr("name")
+            .or_else(|| meta.attr("property"))
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let content = meta.attr("content").unwrap_or_default();
+        if config.meta_weight.is_some() && SUMMARY_META.contains(&name.as_str()) {
+            page_meta.summaries.push(content.to_string());
+        }
+        if name != "robots" || !config.respect_meta_robots {
+            continue;
+        }
+        for directive in content.split(',') {
+            match directive.trim().to_ascii_lowercase().as_str() {
+                "noindex" => page_meta.noindex = true,
+                "nofollow" => page_meta.nofollow = true,
+                "none" => {
+                    page_meta.noindex = true;
+                    page_meta.nofollow = true;
+                }
+                _ => {}
+            }
+        }
+    }
+    page_meta
+}
 
-    let tags = vec![
-        Name("h1"),
-        Name("h2"),
-        Name("h3"),
-        Name("h4"),
-        Name("h5"),
-        Name("h6"),
-        Name("p"),
-        Name("li"),
-        Name("dt"),
-        Name("dd"),
-        Name("blockquote"),
-        Name("q"),
-        Name("cite"),
-        Name("caption"),
-        Name("th"),
-        Name("td"),
-        Name("pre"),
-        Name("code"),
-        Name("strong"),
-        Name("em"),
-        Name("mark"),
-        Name("small"),
-        Name("del"),
-        Name("ins"),
-        Name("sub"),
-        Name("sup"),
-        Name("a"),
-    ];
+// Array-style names with an empty or numeric index (foo[], foo[0]) all become foo[]
+fn normalize_param(name: &str) -> String {
+    match name.find('[') {
+        Some(open) if name.ends_with(']') => {
+            let index = &name[open + 1..name.len() - 1];
+            if index.chars().all(|c| c.is_ascii_digit()) {
+                format!("{}[]", &name[..open])
+            } else {
+                name.to_string()
+            }
+        }
+        _ => name.to_string(),
+    }
+}
 
-    let or_predicate = Or(tags
-        .into_iter()
-        .map(|tag| Box::new(tag) as Box<dyn Predicate>)
-        .collect());
-    let elements = document.find(or_predicate);
+fn record_cookies(url: &Url, headers: &HeaderMap, state: &mut CrawlState) {
+    let host = url.host_str().unwrap_or_default();
+    for set_cookie in headers.get_all(SET_COOKIE) {
+        let cookie = set_cookie.to_str().ok().and_then(cookies::parse);
+        if let Some(cookie) = cookie {
+            state.cookies.insert((host.to_string(), cookie));
+        }
+    }
+}
 
-    let mut word_count = HashMap::new();
-    let link_predicate = Attr("href", ());
+fn record_params(url: &Url, state: &mut CrawlState) {
+    for (name, _) in url.query_pairs() {
+        if !name.is_empty() {
+            state.params.insert(normalize_param(&name));
+        }
+    }
+}
 
-    let common_words_file = File::open(Path::new("src/resources/commonwords.txt"))?;
-    let common_words_reader = BufReader::new(common_words_file);
-    let common_words: HashSet<_> = common_words_reader
-        .lines()
-        .take(config.common_words_limit)
-        .filter_map(Result::ok)
-        .collect();
+const DEFAULT_STRIP_PARAMS: &[&str] = &[
+    "utm_*",
+    "fbclid",
+    "gclid",
+    "dclid",
+    "msclkid",
+    "mc_cid",
+    "mc_eid",
+    "_ga",
+    "_gl",
+    "phpsessid",
+    "jsessionid",
+    "aspsessionid*",
+    "sid",
+    "sessionid",
+];
 
-    let re = Regex::new(r"[^a-zA-Z']+").unwrap();
+// Names ending in '*' match any parameter starting with the rest of the name
+fn strip_param_matches(name: &str, strip_params: &[String]) -> bool {
+    let name = name.to_ascii_lowercase();
+    strip_params
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == *pattern,
+        })
+}
 
-    for node in elements {
-        let text = node.text();
-        let text = text.nfc().collect::<String>();
-
-        for word in text.split_whitespace() {
-            let cleaned_word: String = word.to_lowercase();
-            // Check if the cleaned_word contains any special characters and if it meets the minimum length requirement
-            if !re.is_match(&cleaned_word)
-                && !cleaned_word.is_empty()
-                && !common_words.contains(&cleaned_word)
-                && cleaned_word.len() >= config.min_length
-            {
-                *word_count.entry(cleaned_word).or_insert(0) += 1;
+fn normalize_url(url: &Url, config: &CrawlConfig) -> Url {
+    let mut url = url.clone();
+    let hashbang = url
+        .fragment()
+        .is_some_and(|fragment| fragment.starts_with('!'));
+    if !(config.treat_hashbang_as_page && hashbang) {
+        url.set_fragment(None);
+    }
+    if config.prefer_https && url.scheme() == "http" {
+        let _ = url.set_scheme("https");
+    }
+
+    if url.query().is_some() && !config.strip_params.is_empty() {
+        let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        let kept: Vec<&(String, String)> = pairs
+            .iter()
+            .filter(|(name, _)| !strip_param_matches(name, &config.strip_params))
+            .collect();
+        if kept.len() != pairs.len() {
+            if kept.is_empty() {
+                url.set_query(None);
+            } else {
+                url.query_pairs_mut().clear().extend_pairs(kept);
             }
         }
+    }
+    url
+}
 
-        if depth <= config.max_depth {
-            for link_node in node.find(link_predicate.clone()) {
-                process_node(
-                    &link_node,
-                    url,
-                    depth,
-                    &mut word_count,
-                    visited_urls,
-                    config,
-                );
+const BROWSER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36 Edg/118.0.2088.46",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:109.0) Gecko/20100101 Firefox/118.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Safari/605.1.15",
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1",
+];
+
+fn host_key(url: &Url) -> String {
+    url.host_str().unwrap_or_default().to_string()
+}
+
+// Returns false when requests to the host have been paused
+fn wait_for_host(url: &Url, state: &mut CrawlState, config: &CrawlConfig) -> bool {
+    let throttle = state
+        .throttles
+        .entry(host_key(url))
+        .or_insert_with(|| HostThrottle::new(config.delay, config.jitter));
+    if throttle.paused {
+        return false;
+    }
+    let started = Instant::now();
+    throttle.wait();
+    if let Some(ref mut bucket) = state.rate_limit {
+        bucket.take();
+    }
+    record_time(Phase::Wait, started, state);
+    true
+}
+
+fn record_time(phase: Phase, started: Instant, state: &mut CrawlState) {
+    if let Some(ref mut timings) = state.timings {
+        timings.add(phase, started.elapsed());
+    }
+}
+
+// Returns false when the host asked for a longer pause than is waited for and has been paused
+fn hold_host(url: &Url, pause: Duration, state: &mut CrawlState, config: &CrawlConfig) -> bool {
+    let host = host_key(url);
+    let throttle = state
+        .throttles
+        .entry(host.clone())
+        .or_insert_with(|| HostThrottle::new(config.delay, config.jitter));
+    if throttle.hold(pause) {
+        eprintln!(
+            "Warning: {} asked to wait {}s before the next request (Retry-After), pausing requests to it",
+            host,
+            pause.as_secs()
+        );
+        true
+    } else {
+        eprintln!(
+            "Warning: {} asked to wait {}s (Retry-After), longer than the {}s limit, no further pages will be requested from it",
+            host,
+            pause.as_secs(),
+            throttle::MAX_HOLD.as_secs()
+        );
+        false
+    }
+}
+
+fn record_block(
+    url: &Url,
+    status: StatusCode,
+    headers: &HeaderMap,
+    state: &mut CrawlState,
+    config: &CrawlConfig,
+) {
+    let host = host_key(url);
+    let throttle = state
+        .throttles
+        .entry(host.clone())
+        .or_insert_with(|| HostThrottle::new(config.delay, config.jitter));
+    match throttle.record(throttle::looks_blocked(status, headers)) {
+        Some(BlockAction::Backoff(delay)) => eprintln!(
+            "Warning: {} appears to be blocking requests, backing off to {}s between requests",
+            host,
+            delay.as_secs()
+        ),
+        Some(BlockAction::Pause) => eprintln!(
+            "Warning: {} is still blocking requests, no further pages will be requested from it",
+            host
+        ),
+        None => {}
+    }
+}
+
+// The HTML parser already decodes entities once, this catches text escaped twice (&amp;#64;)
+fn decode_text(text: String, config: &CrawlConfig) -> String {
+    let text = if config.decode_entities {
+        html_escape::decode_html_entities(&text).into_owned()
+    } else {
+        text
+    };
+    if config.percent_decode {
+        percent_encoding::percent_decode_str(&text)
+            .decode_utf8_lossy()
+            .into_owned()
+    } else {
+        text
+    }
+}
+
+// The addresses of a mailto link, or the number of a tel or sms link, without the query
+fn record_contact_link(link: &Url, page: &Url, state: &mut CrawlState, config: &CrawlConfig) {
+    let target = percent_encoding::percent_decode_str(link.path()).decode_utf8_lossy();
+    if link.scheme() == "mailto" {
+        if config.find_emails {
+            for email in config.contacts.find_emails(&target) {
+                state.emails.entry(email).or_default().insert(page.clone());
             }
         }
+    } else if config.find_phones {
+        // Parameters such as ;ext= follow the number itself
+        let phone = target.split(';').next().unwrap_or_default().trim();
+        if phone.chars().any(|c| c.is_ascii_digit()) {
+            state
+                .phones
+                .entry(phone.to_string())
+                .or_default()
+                .insert(page.clone());
+        }
     }
+}
 
-    Ok(word_count)
+fn node_urls(node: &Node, base_url: &Url, config: &CrawlConfig) -> Vec<Url> {
+    std::iter::once("href")
+        .chain(config.data_attrs.iter().map(String::as_str))
+        .filter_map(|attr| node.attr(attr))
+        .filter_map(|link| base_url.join(link.trim()).ok())
+        .collect()
 }
 
-fn unique_words_from_url(
-    url: &str,
+// Whether following the node's links would fetch a page not crawled yet
+fn has_new_page(node: &Node, base_url: &Url, state: &CrawlState, config: &CrawlConfig) -> bool {
+    node_urls(node, base_url, config).iter().any(|url| {
+        matches!(url.scheme(), "http" | "https")
+            && !state.visited_urls.contains(&normalize_url(url, config))
+    })
+}
+
+fn process_node(
+    node: &Node,
+    base_url: &Url,
+    depth: u32,
+    follow: bool,
+    word_count: &mut HashMap<String, u32>,
+    state: &mut CrawlState,
     config: &CrawlConfig,
-) -> Result<HashMap<String, u32>, Box<dyn std::error::Error>> {
-    let parsed_url = Url::parse(url)?;
-    let mut visited_urls = HashSet::new();
-    unique_words_from_url_recursive(&parsed_url, 0, &mut visited_urls, config)
+) {
+    for url in node_urls(node, base_url, config) {
+        // Only web pages are crawled, mailto, tel and sms links go to the contact extractors
+        match url.scheme() {
+            "http" | "https" => {}
+            "mailto" | "tel" | "sms" => {
+                record_contact_link(&url, base_url, state, config);
+                continue;
+            }
+            _ => continue,
+        }
+        let url = normalize_url(&url, config);
+        if config.find_params {
+            record_params(&url, state);
+        }
+        if let Some(ref patterns) = config.sensitive {
+            if let Some(category) = sensitive::category(patterns, &url) {
+                state
+                    .sensitive
+                    .entry(url.clone())
+                    .or_insert_with(|| category.to_string());
+            }
+        }
+        if follow {
+            follow_link(&url, base_url, depth, word_count, state, config);
+        }
+    }
 }
 
-#[derive(Parser, Debug)]
-#[command(name = "harvest")]
-#[command(author = "Ember Hext <github.com/EmberHext")]
-#[command(version = "1.0")]
-#[command(
-    about = "Crawl through a website for interesting words and more",
-    long_about = "Crawl through a website for interesting words, email addresses, and social media links"
-)]
-struct Cli {
-    /// Link to page to search
-    url: String,
-    /// File to output wordlist into
-    #[arg(short, long = "file", value_name = "FILE")]
-    wlfile: Option<String>,
-    /// Do not output a wordlist
-    #[arg(short, long)]
-    nowords: bool,
-    /// Find all emails
-    #[arg(short, long)]
-    email: bool,
-    /// File to output emails into
-    #[arg(long, value_name = "FILE")]
-    emfile: Option<String>,
-    /// Find all socials
-    #[arg(short, long)]
-    social: bool,
-    /// File to output socials into
-    #[arg(long, value_name = "FILE")]
-    socfile: Option<String>,
-    /// Depth to crawl, default is 2
-    #[arg(short, long, value_name = "x")]
-    depth: Option<u8>,
-    /// Minimum word length, default is 4
-    #[arg(short, long, value_name = "x")]
-    min: Option<u8>,
-    /// The number of most common words to filter, default is 400, max is 1000
-    #[arg(short, long, value_name = "x")]
-    common: Option<u8>,
-    /// Allow the crawler to follow external links
-    #[arg(short, long)]
-    offsite: bool,
-    /// User agent to send in http header
-    #[arg(short, long, value_name = "AGENT")]
-    agent: Option<String>,
-    /// Coverty all words to lowercase
-    #[arg(short, long)]
-    lower: bool,
-    /// Parses words that contains diacritics, but removes the diacritics
-    #[arg(short = 'r', long)]
-    diacrit_remove: bool,
+fn internal_host(url: &Url) -> Option<&str> {
+    let host = url.domain()?.trim_end_matches('.');
+    let suffix = host.rsplit('.').next()?.to_ascii_lowercase();
+    (!host.contains('.') || INTERNAL_SUFFIXES.contains(&suffix.as_str())).then_some(host)
 }
 
-fn main() {
-    let url = "https://nytimes.com";
-    let max_depth = 3;
-    let common_words_limit = 1000;
-    let output_file_path = "output.txt";
-    let follow_offsite = false;
-    let min_length = 5;
-    let min_count = 4;
-    let user_agent: Option<String> = Some("Edg/112.0.1722.34".to_string());
-    let headers =
-        headers_from_strings(&["Accept-Charset: iso-8859-5, Unicode-1-1; q = 0,8".to_string()])
-            .unwrap_or_else(|err| {
-                eprintln!("Error: {}", err);
-                std::process::exit(1);
-            });
+// Internal names fail to resolve outside the network, which looks like a dead host unless pointed out
+fn warn_unresolved_internal(hosts: &HashSet<&str>) {
+    if hosts.is_empty() {
+        return;
+    }
+    let mut hosts: Vec<&str> = hosts.iter().copied().collect();
+    hosts.sort_unstable();
+    eprintln!(
+        "Warning: {} could not be resolved; internal names need the internal DNS server, an /etc/hosts entry or a socks5h:// --proxy inside the network, which resolves names on the proxy side (socks5:// resolves them locally)",
+        hosts.join(", ")
+    );
+}
 
-    let config = CrawlConfig {
-        max_depth,
-        common_words_limit,
-        follow_offsite,
-        min_length,
-        user_agent,
-        headers,
-    };
+fn error_kind(err: &(dyn std::error::Error + 'static)) -> &'static str {
+    if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+        if err.is_timeout() {
+            "timeout"
+        } else if err.is_connect() {
+            "connect"
+        } else if err.is_redirect() {
+            "redirect"
+        } else if err.is_body() || err.is_decode() {
+            "body"
+        } else {
+            "request"
+        }
+    } else if err.is::<std::io::Error>() {
+        "io"
+    } else {
+        "other"
+    }
+}
 
-    match unique_words_from_url(url, &config) {
-        Ok(word_count) => {
-            let mut file = File::create(output_file_path).expect("Unable to create file");
+fn record_error(
+    url: &Url,
+    depth: u32,
+    err: &(dyn std::error::Error + 'static),
+    state: &mut CrawlState,
+) {
+    state.errors.push(CrawlError {
+        url: url.clone(),
+        depth,
+        kind: error_kind(err),
+        message: err.to_string(),
+    });
+}
 
-            let mut sorted_word_count: Vec<(&String, &u32)> = word_count.iter().collect();
-            sorted_word_count.sort_by(|a, b| b.1.cmp(a.1));
+fn same_domain(a: Option<&str>, b: Option<&str>, config: &CrawlConfig) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) if config.fuzzy_host => {
+            a.strip_prefix("www.").unwrap_or(a) == b.strip_prefix("www.").unwrap_or(b)
+        }
+        _ => a == b,
+    }
+}
 
-            sorted_word_count = sorted_word_count
-                .into_iter()
-                .filter(|(_, &count)| count >= min_count)
-                .collect();
+// Browsers ignore the header over plain http, where anyone on the path could have added it, and
+// for IP addresses
+fn record_hsts(url: &Url, headers: &HeaderMap, state: &mut CrawlState) {
+    let Some(host) = url.domain() else {
+        return;
+    };
+    let Some(policy) = headers
+        .get(STRICT_TRANSPORT_SECURITY)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return;
+    };
+    let mut max_age = None;
+    let mut include_subdomains = false;
+    for directive in policy.split(';') {
+        let (name, value) = directive.split_once('=').unwrap_or((directive, ""));
+        match name.trim().to_ascii_lowercase().as_str() {
+            "max-age" => max_age = value.trim().trim_matches('"').parse::<u64>().ok(),
+            "includesubdomains" => include_subdomains = true,
+            _ => {}
+        }
+    }
+    let host = host.to_ascii_lowercase();
+    match max_age {
+        // max-age=0 is how a site withdraws its policy
+        Some(0) => {
+            state.hsts_hosts.remove(&host);
+        }
+        Some(_) => {
+            state.hsts_hosts.insert(host, include_subdomains);
+        }
+        None => {}
+    }
+}
 
-            for (word, count) in sorted_word_count {
-                writeln!(file, "{}: {}", word, count).expect("Unable to write data");
-            }
+fn hsts_upgrade(url: &Url, state: &CrawlState) -> Url {
+    let mut url = url.clone();
+    let host = url.domain().unwrap_or_default().to_ascii_lowercase();
+    let known = state
+        .hsts_hosts
+        .iter()
+        .any(|(hsts_host, &include_subdomains)| {
+            host == *hsts_host
+                || (include_subdomains
+                    && host
+                        .strip_suffix(hsts_host.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.')))
+        });
+    if url.scheme() == "http" && known {
+        let _ = url.set_scheme("https");
+    }
+    url
+}
 
-            println!("Results have been written to '{}'", output_file_path);
+fn follow_link(
+    url: &Url,
+    base_url: &Url,
+    depth: u32,
+    word_count: &mut HashMap<String, u32>,
+    state: &mut CrawlState,
+    config: &CrawlConfig,
+) {
+    let url = &hsts_upgrade(url, state);
+    // Crawling a logout link would end the session set up by --login-url
+    if config.cookies.is_some() && login::is_logout(url) {
+        return;
+    }
+    let max_depth = match config.offsite_depth {
+        Some(offsite_depth)
+            if !same_domain(url.domain(), config.seed_domain.as_deref(), config) =>
+        {
+            offsite_depth.min(config.max_depth)
         }
-        Err(e) => {
-            println!("Error: {}", e);
+        _ => config.max_depth,
+    };
+    // The linked page sits one hop further from the seed than the current page
+    if depth < max_depth {
+        let seed_domain = config.seed_domain.as_deref();
+        let in_scope = if config.follow_offsite {
+            true
+        } else if config.offsite_once {
+            // One hop out from the seed domain, external pages only lead back to it
+            same_domain(base_url.domain(), seed_domain, config)
+                || same_domain(url.domain(), seed_domain, config)
+        } else {
+            same_domain(url.domain(), base_url.domain(), config)
+        };
+        if config.graph && (in_scope || config.graph_offsite) && url != base_url {
+            state.edges.insert((base_url.clone(), url.clone()));
         }
+
+        // Only follow the link if follow_offsite is true or if the domains match
+        if in_scope {
+            match unique_words_from_url_recursive(url, depth + 1, state, config) {
+                Ok(new_word_count) => {
+                    for (word, count) in new_word_count {
+                        *word_count.entry(word).or_insert(0) += count;
+                    }
+                }
+                Err(err) => record_error(url, depth + 1, err.as_ref(), state),
+            }
+        }
+    }
+}
+
+// The next page of a listing is crawled at the same depth, pagination does not count as a hop
+fn follow_pagination(
+    document: &Document,
+    url: &Url,
+    depth: u32,
+    word_count: &mut HashMap<String, u32>,
+    state: &mut CrawlState,
+    config: &CrawlConfig,
+) {
+    let max_pages = match config.max_pagination {
+        Some(max_pages) => max_pages,
+        None => return,
+    };
+    let position = state.pagination.get(url).copied().unwrap_or(0);
+    if position >= max_pages {
+        return;
+    }
+
+    let pages: Vec<Url> = document
+        .find(Name("link").or(Name("a")))
+        .filter(|node| {
+            node.attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("next") || rel.eq_ignore_ascii_case("prev"))
+            })
+        })
+        .filter_map(|node| node.attr("href"))
+        .filter_map(|href| url.join(href.trim()).ok())
+        .map(|page| normalize_url(&page, config))
+        .collect();
+    for page in pages {
+        // Pages already crawled are skipped, which also stops next and prev from looping
+        if state.visited_urls.contains(&page)
+            || !(config.follow_offsite || same_domain(page.domain(), url.domain(), config))
+        {
+            continue;
+        }
+        state.pagination.insert(page.clone(), position + 1);
+        if config.graph {
+            state.edges.insert((url.clone(), page.clone()));
+        }
+        match unique_words_from_url_recursive(&page, depth, state, config) {
+            Ok(new_word_count) => {
+                for (word, count) in new_word_count {
+                    *word_count.entry(word).or_insert(0) += count;
+                }
+            }
+            Err(err) => record_error(&page, depth, err.as_ref(), state),
+        }
+    }
+}
+
+fn record_subdomains(
+    document: &Document,
+    url: &Url,
+    matcher: &SubdomainMatcher,
+    state: &mut CrawlState,
+) {
+    let mut urls = vec![url.clone()];
+    urls.extend(
+        document
+            .find(Attr("href", ()).or(Attr("src", ())))
+            .filter_map(|node| node.attr("href").or_else(|| node.attr("src")))
+            .filter_map(|link| url.join(link.trim()).ok()),
+    );
+    urls.extend(
+        document
+            .find(Name("meta"))
+            .filter(|node| {
+                node.attr("property")
+                    .is_some_and(|property| property.starts_with("og:"))
+            })
+            .filter_map(|node| node.attr("content"))
+            .filter_map(|content| Url::parse(content.trim()).ok()),
+    );
+    for block in jsonld::blocks(document) {
+        let mut findings = jsonld::Findings::default();
+        jsonld::extract(&block, &mut findings);
+        urls.extend(
+            findings
+                .same_as
+                .iter()
+                .filter_map(|link| Url::parse(link).ok()),
+        );
+    }
+
+    let mut found: Vec<String> = urls
+        .iter()
+        .filter_map(|url| url.host_str())
+        .filter_map(|host| matcher.subdomain(host))
+        .collect();
+    for script in document.find(Name("script")) {
+        found.extend(matcher.find(&script.text()));
+    }
+    state.subdomains.extend(found);
+}
+
+// A heuristic: only markup is checked, so elements hidden by stylesheets or scripts are missed
+fn is_hidden(node: &Node) -> bool {
+    let mut current = Some(*node);
+    while let Some(element) = current {
+        let style = element
+            .attr("style")
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .replace(char::is_whitespace, "");
+        if element.attr("hidden").is_some()
+            || element
+                .attr("aria-hidden")
+                .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"))
+            || style.contains("display:none")
+            || style.contains("visibility:hidden")
+        {
+            return true;
+        }
+        current = element.parent();
+    }
+    false
+}
+
+fn canonical_url(document: &Document, page_url: &Url, config: &CrawlConfig) -> Url {
+    document
+        .find(Name("link"))
+        .filter(|link| {
+            link.attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|value| value.eq_ignore_ascii_case("canonical"))
+            })
+        })
+        .filter_map(|link| link.attr("href"))
+        .find_map(|href| page_url.join(href.trim()).ok())
+        .map(|canonical| normalize_url(&canonical, config))
+        .unwrap_or_else(|| page_url.clone())
+}
+
+struct Form {
+    action: Url,
+    method: String,
+    fields: Vec<String>,
+}
+
+fn page_forms(document: &Document, page_url: &Url) -> Vec<Form> {
+    let field_predicate = Name("input")
+        .or(Name("select"))
+        .or(Name("textarea"))
+        .or(Name("button"));
+
+    document
+        .find(Name("form"))
+        .filter_map(|form| {
+            // A missing or empty action submits back to the page itself
+            let action = match form.attr("action").map(str::trim) {
+                Some(action) if !action.is_empty() => page_url.join(action).ok()?,
+                _ => page_url.clone(),
+            };
+            let method = form.attr("method").unwrap_or("get").trim().to_uppercase();
+            let fields = form
+                .find(field_predicate)
+                .filter_map(|field| field.attr("name"))
+                .map(str::to_string)
+                .collect();
+            Some(Form {
+                action,
+                method,
+                fields,
+            })
+        })
+        .collect()
+}
+
+fn http_client(
+    url: &Url,
+    config: &CrawlConfig,
+) -> Result<reqwest::blocking::Client, Box<dyn std::error::Error>> {
+    let mut req_headers = config.headers.clone();
+    if let Some(ref agent) = config.user_agent {
+        req_headers.insert(USER_AGENT, HeaderValue::from_str(agent)?);
+    } else if config.rotate_agent {
+        let agent = BROWSER_AGENTS[rand::thread_rng().gen_range(0..BROWSER_AGENTS.len())];
+        req_headers.insert(USER_AGENT, HeaderValue::from_static(agent));
+    }
+
+    for (name, template) in &config.header_templates {
+        let value = render_template(template, &config.seed_host);
+        req_headers.insert(name.clone(), HeaderValue::from_str(&value)?);
+    }
+
+    let mut client_builder = reqwest::blocking::Client::builder().default_headers(req_headers);
+    if let Some(version) = config.min_tls {
+        // native-tls cannot enforce TLS 1.3 as a minimum, so rustls is used for it instead
+        if version == tls::Version::TLS_1_3 {
+            client_builder = client_builder.use_rustls_tls();
+        }
+        client_builder = client_builder.min_tls_version(version);
+    }
+    for certificate in &config.ca_certs {
+        client_builder = client_builder.add_root_certificate(certificate.clone());
+    }
+    if config.accept_hostname_mismatch {
+        client_builder = client_builder.danger_accept_invalid_hostnames(true);
+    }
+    if let Some(ref proxy) = config.proxy {
+        client_builder = client_builder.proxy(proxy.clone());
+    }
+    // HTTP/2 is otherwise used whenever the server offers it during the TLS handshake
+    if config.http1_only {
+        client_builder = client_builder.http1_only();
+    }
+    if let Some(ref cookies) = config.cookies {
+        client_builder = client_builder.cookie_provider(cookies.clone());
+    }
+    if let Some(ref resolver) = config.resolver {
+        client_builder = resolver.pin(client_builder, url)?;
+    }
+    Ok(client_builder.build()?)
+}
+
+// The host and port, since a host may serve https on one port and only http on another
+fn authority(url: &Url) -> String {
+    format!(
+        "{}:{}",
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default().unwrap_or_default()
+    )
+}
+
+fn plain_http(url: &Url, state: &CrawlState) -> Url {
+    let mut url = url.clone();
+    if url.scheme() == "https" && state.plain_http_hosts.contains(&authority(&url)) {
+        let _ = url.set_scheme("http");
+    }
+    url
+}
+
+// Where pages come from: the network, a headless browser with --render, a corpus with --replay
+// or standard input with --stdin. None is a page that was skipped.
+trait Fetcher {
+    fn fetch(
+        &self,
+        url: &Url,
+        depth: u32,
+        state: &mut CrawlState,
+        config: &CrawlConfig,
+    ) -> Result<Option<StoredPage>, Box<dyn std::error::Error>>;
+}
+
+struct HttpFetcher;
+
+impl Fetcher for HttpFetcher {
+    fn fetch(
+        &self,
+        url: &Url,
+        depth: u32,
+        state: &mut CrawlState,
+        config: &CrawlConfig,
+    ) -> Result<Option<StoredPage>, Box<dyn std::error::Error>> {
+        fetch_page(url, depth, state, config)
+    }
+}
+
+// Pages are still fetched over HTTP first, so status codes, headers, the cache and all the
+// filtering on them work as usual, then HTML pages are loaded again in the browser
+#[cfg(feature = "render")]
+struct BrowserFetcher(render::Browser);
+
+#[cfg(feature = "render")]
+impl Fetcher for BrowserFetcher {
+    fn fetch(
+        &self,
+        url: &Url,
+        depth: u32,
+        state: &mut CrawlState,
+        config: &CrawlConfig,
+    ) -> Result<Option<StoredPage>, Box<dyn std::error::Error>> {
+        match fetch_page(url, depth, state, config)? {
+            Some(mut page) if is_html_content(&page.headers) => {
+                page.body = self
+                    .0
+                    .render(url, config.user_agent.as_deref())?
+                    .into_bytes();
+                Ok(Some(page))
+            }
+            page => Ok(page),
+        }
+    }
+}
+
+struct ReplayFetcher(Corpus);
+
+impl Fetcher for ReplayFetcher {
+    fn fetch(
+        &self,
+        url: &Url,
+        _depth: u32,
+        state: &mut CrawlState,
+        _config: &CrawlConfig,
+    ) -> Result<Option<StoredPage>, Box<dyn std::error::Error>> {
+        let page = self.0.load(url)?;
+        if let Some(ref page) = page {
+            state.links.push((url.clone(), page.status.as_u16()));
+        }
+        Ok(page)
+    }
+}
+
+struct StdinFetcher;
+
+impl Fetcher for StdinFetcher {
+    fn fetch(
+        &self,
+        url: &Url,
+        _depth: u32,
+        state: &mut CrawlState,
+        _config: &CrawlConfig,
+    ) -> Result<Option<StoredPage>, Box<dyn std::error::Error>> {
+        Ok(stdin_page(url, state))
+    }
+}
+
+fn fetch_page(
+    url: &Url,
+    depth: u32,
+    state: &mut CrawlState,
+    config: &CrawlConfig,
+) -> Result<Option<StoredPage>, Box<dyn std::error::Error>> {
+    if !config.prefer_https {
+        return fetch_url(url, depth, state, config);
+    }
+    let target = plain_http(url, state);
+    match fetch_url(&target, depth, state, config) {
+        Err(err)
+            if target.scheme() == "https"
+                && err
+                    .downcast_ref::<reqwest::Error>()
+                    .is_some_and(reqwest::Error::is_connect) =>
+        {
+            eprintln!(
+                "Warning: {} does not answer over https, using http for it",
+                authority(&target)
+            );
+            state.plain_http_hosts.insert(authority(&target));
+            fetch_url(&plain_http(url, state), depth, state, config)
+        }
+        result => result,
+    }
+}
+
+fn fetch_url(
+    url: &Url,
+    depth: u32,
+    state: &mut CrawlState,
+    config: &CrawlConfig,
+) -> Result<Option<StoredPage>, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let client = http_client(url, config)?;
+    record_time(Phase::Setup, started, state);
+
+    if !wait_for_host(url, state, config) {
+        return Ok(None);
+    }
+
+    if config.preflight {
+        let started = Instant::now();
+        let preflight = preflight_status(&client, url, config);
+        record_time(Phase::Request, started, state);
+        if let Some(status) = preflight? {
+            state.links.push((url.clone(), status.as_u16()));
+            return Ok(None);
+        }
+        if !wait_for_host(url, state, config) {
+            return Ok(None);
+        }
+    }
+
+    let cached = config.cache.as_ref().and_then(|cache| cache.load(url));
+    let mut retried = false;
+    let mut resp = loop {
+        let mut request = client.request(Method::GET, url.as_str());
+        if let Some(ref entry) = cached {
+            request = request.headers(entry.conditional_headers());
+        }
+        let started = Instant::now();
+        let resp = request.send();
+        record_time(Phase::Request, started, state);
+        let resp = resp?;
+        let pause = config
+            .retry
+            .retry_delay(&Method::GET, resp.status(), resp.headers());
+        if let Some(pause) = pause {
+            if hold_host(url, pause, state, config) && !retried {
+                retried = true;
+                wait_for_host(url, state, config);
+                continue;
+            }
+        }
+        break resp;
+    };
+
+    let status = resp.status();
+    state.links.push((url.clone(), status.as_u16()));
+    // Redirects are followed by the client, so scope is only known once they have all been taken
+    let landed = resp.url();
+    if config.strict_scope
+        && !same_domain(landed.domain(), url.domain(), config)
+        && !same_domain(landed.domain(), config.seed_domain.as_deref(), config)
+    {
+        state.errors.push(CrawlError {
+            url: url.clone(),
+            depth,
+            kind: "scope",
+            message: format!("redirected out of scope to {}", landed),
+        });
+        return Ok(None);
+    }
+    if landed.scheme() == "https" {
+        record_hsts(landed, resp.headers(), state);
+    }
+    if status.is_client_error() || status.is_server_error() {
+        state.errors.push(CrawlError {
+            url: url.clone(),
+            depth,
+            kind: "http",
+            message: status.to_string(),
+        });
+    }
+    let response_headers = resp.headers().clone();
+    if config.backoff_on_block {
+        record_block(url, status, &response_headers, state, config);
+    }
+
+    let body = match cached {
+        Some(entry) if status == StatusCode::NOT_MODIFIED => entry.body,
+        _ => {
+            if !should_parse_status(status, &config.parse_status)
+                || exceeds_body_limit(resp.headers(), config.max_body_size)
+            {
+                return Ok(None);
+            }
+
+            let mut body = Vec::new();
+            let started = Instant::now();
+            // Bodies without a Content-Length are cut off at the limit rather than read in full.
+            // One byte more than the limit is read to tell a cut off body from one that just fits
+            let read = match config.max_body_size {
+                Some(max) => resp.take(max.saturating_add(1)).read_to_end(&mut body),
+                None => resp.read_to_end(&mut body),
+            };
+            record_time(Phase::Download, started, state);
+            read?;
+            let mut truncated = false;
+            if let Some(max) = config.max_body_size {
+                truncated = body.len() as u64 > max;
+                body.truncate(max as usize);
+            }
+            // Only whole pages are cached, a 304 would otherwise serve a cut off or error body
+            if let Some(ref cache) = config.cache {
+                if status == StatusCode::OK && !truncated {
+                    cache.store(url, &response_headers, &body)?;
+                }
+            }
+            body
+        }
+    };
+
+    Ok(Some(StoredPage {
+        status,
+        headers: response_headers,
+        body,
+    }))
+}
+
+// Images are capped at this size unless --max-body-size sets a limit
+const IMAGE_MAX_BYTES: u64 = 20 * 1024 * 1024;
+
+fn fetch_image_exif(
+    url: &Url,
+    state: &mut CrawlState,
+    config: &CrawlConfig,
+) -> Result<Option<ExifFields>, Box<dyn std::error::Error>> {
+    if !images::may_have_exif(url) {
+        return Ok(None);
+    }
+    let client = http_client(url, config)?;
+    if !wait_for_host(url, state, config) {
+        return Ok(None);
+    }
+    let resp = client.get(url.as_str()).send()?;
+    let max = config.max_body_size.unwrap_or(IMAGE_MAX_BYTES);
+    let is_image = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.to_ascii_lowercase().starts_with("image/"));
+    if !resp.status().is_success() || !is_image || exceeds_body_limit(resp.headers(), Some(max)) {
+        return Ok(None);
+    }
+    let mut body = Vec::new();
+    resp.take(max).read_to_end(&mut body)?;
+    Ok(images::read_exif(&body))
+}
+
+fn images_json(
+    images: &HashMap<String, HashSet<Url>>,
+    exif: Option<&HashMap<String, ExifFields>>,
+) -> serde_json::Value {
+    let mut output = sources_json("url", images);
+    if let Some(exif) = exif {
+        for entry in output.as_array_mut().into_iter().flatten() {
+            let fields = entry["url"].as_str().and_then(|url| exif.get(url));
+            entry["exif"] = match fields {
+                Some(fields) => fields
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), json!(value)))
+                    .collect::<serde_json::Map<_, _>>()
+                    .into(),
+                None => serde_json::Value::Null,
+            };
+        }
+    }
+    output
+}
+
+// Stands in for fetching with --stdin: the seed is the page read, and every other URL is skipped
+fn stdin_page(url: &Url, state: &mut CrawlState) -> Option<StoredPage> {
+    if state
+        .stdin_page
+        .as_ref()
+        .is_some_and(|(seed, _)| seed != url)
+    {
+        return None;
+    }
+    let (_, body) = state.stdin_page.take()?;
+    state.links.push((url.clone(), StatusCode::OK.as_u16()));
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+    Some(StoredPage {
+        status: StatusCode::OK,
+        headers,
+        body,
+    })
+}
+
+// The parsing and extraction time of a page leaves out the pages it leads on to, which are
+// timed on their own
+fn unique_words_from_url_recursive(
+    url: &Url,
+    depth: u32,
+    state: &mut CrawlState,
+    config: &CrawlConfig,
+) -> Result<HashMap<String, u32>, Box<dyn std::error::Error>> {
+    let parsing = pause_parsing(state);
+    let result = crawl_page(url, depth, state, config);
+    pause_parsing(state);
+    if parsing {
+        state.parse_started = Some(Instant::now());
+    }
+    result
+}
+
+// Whether a page was being parsed, its time so far is recorded
+fn pause_parsing(state: &mut CrawlState) -> bool {
+    match state.parse_started.take() {
+        Some(started) => {
+            record_time(Phase::Parse, started, state);
+            true
+        }
+        None => false,
+    }
+}
+
+fn crawl_page(
+    url: &Url,
+    depth: u32,
+    state: &mut CrawlState,
+    config: &CrawlConfig,
+) -> Result<HashMap<String, u32>, Box<dyn std::error::Error>> {
+    #[cfg(feature = "tui")]
+    if state
+        .dashboard
+        .as_ref()
+        .is_some_and(|dashboard| dashboard.quit)
+    {
+        return Ok(HashMap::new());
+    }
+    if !state.visited_urls.insert(url.clone()) {
+        // If the URL is already in the visited set, return an empty HashMap
+        return Ok(HashMap::new());
+    }
+
+    let page = config.fetcher.fetch(url, depth, state, config)?;
+    let StoredPage {
+        status,
+        headers: response_headers,
+        body,
+    } = match page {
+        Some(page) => page,
+        None => return Ok(HashMap::new()),
+    };
+    if let Some(ref corpus) = config.dump {
+        corpus.store(url, status, &response_headers, &body)?;
+    }
+    if config.report_cookies {
+        record_cookies(url, &response_headers, state);
+    }
+    if config.report_headers {
+        state
+            .header_report
+            .entry(url.host_str().unwrap_or_default().to_string())
+            .or_default()
+            .record(url, &response_headers);
+    }
+    if config
+        .min_content_length
+        .is_some_and(|min| (body.len() as u64) < min)
+    {
+        return Ok(HashMap::new());
+    }
+    if state.timings.is_some() {
+        state.parse_started = Some(Instant::now());
+    }
+
+    let body = if config.parse_pdf && pdf::is_pdf(&response_headers, &body) {
+        match pdf::extract_text(&body) {
+            Ok(text) if !text.trim().is_empty() => pdf::as_html(&text).into_bytes(),
+            Ok(_) => {
+                eprintln!(
+                    "Warning: Skipping PDF '{}', it has no text layer (scanned images?)",
+                    url
+                );
+                return Ok(HashMap::new());
+            }
+            Err(err) => {
+                eprintln!(
+                    "Warning: Skipping PDF '{}': {}",
+                    url,
+                    escape_controls(&err.to_string())
+                );
+                return Ok(HashMap::new());
+            }
+        }
+    } else if config.feeds && feeds::is_feed(&response_headers, &body) {
+        let crawled = |link: &Url| state.visited_urls.contains(&normalize_url(link, config));
+        match feeds::as_html(&body, url, crawled) {
+            Ok(html) => html.into_bytes(),
+            Err(err) => {
+                eprintln!(
+                    "Warning: Skipping feed '{}': {}",
+                    url,
+                    escape_controls(&err)
+                );
+                return Ok(HashMap::new());
+            }
+        }
+    } else {
+        body
+    };
+
+    let document = Document::from(String::from_utf8_lossy(&body).as_ref());
+
+    // The visited set only stops the same URL being fetched twice, this also stops different URLs
+    // of the same content being extracted twice
+    if config.canonical_dedup
+        && !state
+            .canonicals
+            .insert(canonical_url(&document, url, config))
+    {
+        return Ok(HashMap::new());
+    }
+    if let Some(threshold) = config.skip_similar {
+        let words = config.tokenizer.words(&simhash::page_text(&document));
+        let fingerprint = simhash::fingerprint(&words);
+        if let Some(similar) = state.seen_pages.check(fingerprint, url, threshold) {
+            state.similar_to.insert(url.clone(), similar);
+            return Ok(HashMap::new());
+        }
+    }
+    state.parsed_pages += 1;
+
+    let meta = page_meta(&document, config);
+
+    if let Some(ref signatures) = config.tech {
+        for detection in tech::detect(signatures, &response_headers, &document) {
+            state
+                .technologies
+                .entry(detection.technology)
+                .or_insert(detection.evidence);
+        }
+    }
+
+    if config.find_socials {
+        for href in document
+            .find(Attr("href", ()))
+            .filter_map(|n| n.attr("href"))
+        {
+            if let Some(account) = url.join(href).ok().and_then(|link| social_account(&link)) {
+                state
+                    .socials
+                    .entry(account)
+                    .or_default()
+                    .insert(url.clone());
+            }
+        }
+    }
+
+    if let Some(ref matcher) = config.subdomains {
+        record_subdomains(&document, url, matcher, state);
+    }
+
+    if config.find_images {
+        for image in images::image_urls(&document, url) {
+            let image = normalize_url(&image, config);
+            state
+                .images
+                .entry(image.to_string())
+                .or_default()
+                .insert(url.clone());
+        }
+    }
+
+    if config.jsonld {
+        for block in jsonld::blocks(&document) {
+            let mut findings = jsonld::Findings::default();
+            jsonld::extract(&block, &mut findings);
+            for name in findings.names.into_iter().filter(|name| !name.is_empty()) {
+                state.names.entry(name).or_default().insert(url.clone());
+            }
+            for email in findings
+                .emails
+                .into_iter()
+                .filter(|email| plausible_email(email))
+            {
+                state.emails.entry(email).or_default().insert(url.clone());
+            }
+            for phone in findings
+                .phones
+                .into_iter()
+                .filter(|phone| !phone.is_empty())
+            {
+                state.phones.entry(phone).or_default().insert(url.clone());
+            }
+            for link in findings.same_as {
+                if let Some(account) = Url::parse(&link)
+                    .ok()
+                    .and_then(|link| social_account(&link))
+                {
+                    state
+                        .socials
+                        .entry(account)
+                        .or_default()
+                        .insert(url.clone());
+                }
+            }
+            if config.jsonld_raw {
+                state.jsonld_blocks.push((url.clone(), block));
+            }
+        }
+    }
+
+    let tags = vec![
+        Name("h1"),
+        Name("h2"),
+        Name("h3"),
+        Name("h4"),
+        Name("h5"),
+        Name("h6"),
+        Name("p"),
+        Name("li"),
+        Name("dt"),
+        Name("dd"),
+        Name("blockquote"),
+        Name("q"),
+        Name("cite"),
+        Name("caption"),
+        Name("th"),
+        Name("td"),
+        Name("pre"),
+        Name("code"),
+        Name("strong"),
+        Name("em"),
+        Name("mark"),
+        Name("small"),
+        Name("del"),
+        Name("ins"),
+        Name("sub"),
+        Name("sup"),
+        Name("a"),
+    ];
+
+    let or_predicate = Or(tags
+        .into_iter()
+        .map(|tag| Box::new(tag) as Box<dyn Predicate>)
+        .collect());
+    let elements = document.find(or_predicate);
+
+    let mut word_count = HashMap::new();
+    let mut page_words = HashMap::new();
+
+    if !meta.nofollow {
+        follow_pagination(&document, url, depth, &mut word_count, state, config);
+    }
+    let link_predicate = |node: &Node| {
+        (node.name() == Some("a") && node.attr("href").is_some())
+            || config
+                .data_attrs
+                .iter()
+                .any(|attr| node.attr(attr).is_some())
+    };
+
+    let common_words_file = File::open(Path::new("src/resources/commonwords.txt"))?;
+    let common_words = common_words(BufReader::new(common_words_file), config.common_words_limit);
+
+    let mut position = 0;
+    if config.count_words && config.prominence && !meta.noindex {
+        let title = document
+            .find(Name("title"))
+            .next()
+            .map(|title| {
+                config
+                    .tokenizer
+                    .normalize(&decode_text(title.text(), config))
+            })
+            .unwrap_or_default();
+        let title_words: HashSet<String> = config.tokenizer.words(&title).into_iter().collect();
+        for word in title_words {
+            if !common_words.contains(&word) && tokenizer::word_length(&word) >= config.min_length {
+                *state.scores.entry(word).or_insert(0.0) += prominence::TITLE_WEIGHT;
+            }
+        }
+    }
+
+    if let Some(weight) = config
+        .meta_weight
+        .filter(|_| config.count_words && !meta.noindex)
+    {
+        for summary in &meta.summaries {
+            let text = config
+                .tokenizer
+                .normalize(&decode_text(summary.clone(), config));
+            for word in config.tokenizer.words(&text) {
+                if !common_words.contains(&word)
+                    && tokenizer::word_length(&word) >= config.min_length
+                {
+                    if config.prominence {
+                        *state.scores.entry(word.clone()).or_insert(0.0) += weight as f64;
+                    }
+                    *page_words.entry(word).or_insert(0) += weight;
+                }
+            }
+        }
+    }
+
+    for node in elements {
+        let counted =
+            config.count_words && !meta.noindex && !(config.exclude_hidden && is_hidden(&node));
+        for chunk in TextChunks::new(&node) {
+            let text = decode_text(chunk, config);
+            let text = config.tokenizer.normalize(&text);
+
+            if config.find_emails {
+                for email in config.contacts.find_emails(&text) {
+                    state.emails.entry(email).or_default().insert(url.clone());
+                }
+            }
+            if config.find_socials {
+                for account in config.contacts.find_socials(&text) {
+                    state
+                        .socials
+                        .entry(account)
+                        .or_default()
+                        .insert(url.clone());
+                }
+            }
+
+            if let Some(ref matcher) = config.crypto {
+                for (currency, address) in matcher.find(&text) {
+                    state.crypto.entry(currency).or_default().insert(address);
+                }
+            }
+
+            if counted {
+                for cleaned_word in config.tokenizer.words(&text) {
+                    if !common_words.contains(&cleaned_word)
+                        && tokenizer::word_length(&cleaned_word) >= config.min_length
+                    {
+                        if config.prominence {
+                            let tag = node.name().unwrap_or_default();
+                            *state.scores.entry(cleaned_word.clone()).or_insert(0.0) +=
+                                prominence::occurrence_score(tag, position);
+                            position += 1;
+                        }
+                        *page_words.entry(cleaned_word).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    // Every page's links feed the contact, parameter and sensitive path extractors, but links are
+    // not followed from pages at the depth limit, so --depth 0 fetches the seed only
+    let follow = depth < config.max_depth && !meta.nofollow;
+    let mut link_nodes: Vec<Node> = document.find(link_predicate).collect();
+    if follow {
+        if let Some(ref mut rng) = state.shuffle {
+            link_nodes.shuffle(rng);
+        }
+    }
+    let mut new_pages = 0;
+    let mut capped = 0;
+    for link_node in link_nodes {
+        let mut follow_node = follow;
+        if let (true, Some(max)) = (follow, config.max_links_per_page) {
+            if has_new_page(&link_node, url, state, config) {
+                if new_pages >= max {
+                    capped += 1;
+                    follow_node = false;
+                } else {
+                    new_pages += 1;
+                }
+            }
+        }
+        process_node(
+            &link_node,
+            url,
+            depth,
+            follow_node,
+            &mut word_count,
+            state,
+            config,
+        );
+    }
+    if capped > 0 {
+        eprintln!(
+            "Warning: Not following {} more links on '{}', --max-links-per-page reached",
+            capped, url
+        );
+    }
+    // After the page's own links, so articles it links to are crawled before the feed
+    if config.feeds && !meta.nofollow {
+        for feed in feeds::feed_links(&document, url) {
+            let feed = normalize_url(&feed, config);
+            follow_link(&feed, url, depth, &mut word_count, state, config);
+        }
+    }
+
+    if config.find_params {
+        record_params(url, state);
+    }
+
+    if config.find_forms || config.follow_forms || config.find_params {
+        for form in page_forms(&document, url) {
+            let follow = config.follow_forms && form.method == "GET" && !meta.nofollow;
+            if config.find_params {
+                record_params(&form.action, state);
+                for field in &form.fields {
+                    state.params.insert(normalize_param(field));
+                }
+            }
+            if config.find_forms {
+                state
+                    .forms
+                    .entry((form.action.clone(), form.method))
+                    .or_default()
+                    .extend(form.fields);
+            }
+            if follow {
+                let action = normalize_url(&form.action, config);
+                follow_link(&action, url, depth, &mut word_count, state, config);
+            }
+        }
+    }
+
+    if config.follow_offsite || config.offsite_once {
+        let host = state.hosts.entry(host_key(url)).or_default();
+        host.parsed += 1;
+        host.words.extend(page_words.keys().cloned());
+    }
+    if config.track_word_pages {
+        for word in page_words.keys() {
+            *state.word_pages.entry(word.clone()).or_insert(0) += 1;
+        }
+    }
+    if let Some(max) = config.word_sources {
+        for word in page_words.keys() {
+            let sources = state.word_sources.entry(word.clone()).or_default();
+            if sources.len() < max {
+                sources.push(url.clone());
+            }
+        }
+    }
+    for (word, count) in &page_words {
+        let count = if config.document_frequency { 1 } else { *count };
+        match state.approx {
+            Some(ref mut approx) => approx.add(word, count),
+            None => *word_count.entry(word.clone()).or_insert(0) += count,
+        }
+    }
+    #[cfg(feature = "tui")]
+    if let Some(ref mut dashboard) = state.dashboard {
+        dashboard.add_words(&page_words);
+        dashboard.update(&tui::Snapshot {
+            depth,
+            parsed: state.parsed_pages,
+            errors: state.errors.len(),
+            fetched: &state.links,
+        });
+    }
+    if config.by_depth {
+        let depth = depth as usize;
+        if state.depths.len() <= depth {
+            state.depths.resize_with(depth + 1, DepthWords::default);
+        }
+        let depth_words = &mut state.depths[depth];
+        depth_words.pages += 1;
+        for (word, count) in &page_words {
+            *depth_words.words.entry(word.clone()).or_insert(0) += count;
+        }
+    }
+    if config.keep_pages {
+        state.pages.push(PageResult {
+            url: url.clone(),
+            words: page_words,
+        });
+    }
+
+    Ok(word_count)
+}
+
+fn unique_words_from_url(
+    url: &Url,
+    state: &mut CrawlState,
+    config: &CrawlConfig,
+) -> Result<HashMap<String, u32>, Box<dyn std::error::Error>> {
+    let parsed_url = normalize_url(url, config);
+    let result = unique_words_from_url_recursive(&parsed_url, 0, state, config);
+    if let Err(ref err) = result {
+        record_error(&parsed_url, 0, err.as_ref(), state);
+    }
+    result
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum SortOrder {
+    /// Most frequent first
+    Count,
+    /// Alphabetical
+    Alpha,
+    /// Shortest first
+    Length,
+    /// Least frequent first
+    CountAsc,
+}
+
+impl SortOrder {
+    // Ties are always broken alphabetically so output is stable between runs
+    fn compare(&self, a: &(&String, &u32), b: &(&String, &u32)) -> Ordering {
+        let order = match self {
+            SortOrder::Count => b.1.cmp(a.1),
+            SortOrder::Alpha => Ordering::Equal,
+            SortOrder::Length => a.0.chars().count().cmp(&b.0.chars().count()),
+            SortOrder::CountAsc => a.1.cmp(b.1),
+        };
+        order.then_with(|| a.0.cmp(b.0))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Rank {
+    /// By number of occurrences, in the order given by --sort
+    Count,
+    /// By prominence score, highest first
+    Prominence,
+    /// By count weighted by how rare the word is in general English, highest first
+    Tfidf,
+}
+
+// Words are always lowercased, so entries are too, or capitalised ones would never match
+fn common_words(reader: impl BufRead, limit: usize) -> HashSet<String> {
+    reader
+        .lines()
+        .take(limit)
+        .filter_map(Result::ok)
+        .map(|word| word.trim().to_lowercase())
+        .collect()
+}
+
+fn rank_by_score(words: &mut [(&String, &u32)], scores: &HashMap<String, f64>) {
+    let score = |word: &String| scores.get(word).copied().unwrap_or(0.0);
+    words.sort_by(|a, b| score(b.0).total_cmp(&score(a.0)).then_with(|| a.0.cmp(b.0)));
+}
+
+fn sorted_words(
+    word_count: &HashMap<String, u32>,
+    min_count: u32,
+    sort: SortOrder,
+) -> Vec<(&String, &u32)> {
+    let mut sorted_word_count: Vec<(&String, &u32)> = word_count.iter().collect();
+    sorted_word_count.sort_by(|a, b| sort.compare(a, b));
+    sorted_word_count.retain(|(_, &count)| count >= min_count);
+    sorted_word_count
+}
+
+// The wordlist in the order every output shares, by score rather than --sort when ranked
+fn ranked_words<'a>(
+    word_count: &'a HashMap<String, u32>,
+    min_count: u32,
+    sort: SortOrder,
+    scores: Option<&HashMap<String, f64>>,
+) -> Vec<(&'a String, &'a u32)> {
+    let mut words = sorted_words(word_count, min_count, sort);
+    if let Some(scores) = scores {
+        rank_by_score(&mut words, scores);
+    }
+    words
+}
+
+fn top_words<'a>(
+    mut words: Vec<(&'a String, &'a u32)>,
+    top: Option<usize>,
+) -> Vec<(&'a String, &'a u32)> {
+    if let Some(top) = top {
+        words.truncate(top);
+    }
+    words
+}
+
+fn words_json(
+    words: &[(&String, &u32)],
+    scores: Option<&HashMap<String, f64>>,
+    sources: Option<&HashMap<String, Vec<Url>>>,
+) -> serde_json::Value {
+    words
+        .iter()
+        .map(|(word, count)| {
+            let mut entry = match scores {
+                Some(scores) => {
+                    let score = scores.get(*word).copied().unwrap_or(0.0);
+                    json!({ "word": word, "count": count, "score": (score * 100.0).round() / 100.0 })
+                }
+                None => json!({ "word": word, "count": count }),
+            };
+            if let Some(sources) = sources {
+                let urls: Vec<&str> = sources
+                    .get(*word)
+                    .into_iter()
+                    .flatten()
+                    .map(Url::as_str)
+                    .collect();
+                entry["sources"] = json!(urls);
+            }
+            entry
+        })
+        .collect()
+}
+
+fn sorted_crypto(crypto: &HashMap<Currency, HashSet<String>>) -> Vec<(Currency, Vec<&String>)> {
+    let mut sorted: Vec<(Currency, Vec<&String>)> = crypto
+        .iter()
+        .map(|(currency, addresses)| {
+            let mut addresses: Vec<&String> = addresses.iter().collect();
+            addresses.sort();
+            (*currency, addresses)
+        })
+        .collect();
+    sorted.sort_by_key(|(currency, _)| *currency);
+    sorted
+}
+
+fn sorted_set(set: &HashSet<String>) -> Vec<&String> {
+    let mut sorted: Vec<&String> = set.iter().collect();
+    sorted.sort();
+    sorted
+}
+
+fn sorted_keys<V>(map: &HashMap<String, V>) -> Vec<&String> {
+    let mut sorted: Vec<&String> = map.keys().collect();
+    sorted.sort();
+    sorted
+}
+
+fn sources_json(key: &str, found: &HashMap<String, HashSet<Url>>) -> serde_json::Value {
+    sorted_keys(found)
+        .into_iter()
+        .map(|value| {
+            let mut sources: Vec<&str> = found[value].iter().map(Url::as_str).collect();
+            sources.sort();
+            json!({ key: value, "sources": sources })
+        })
+        .collect()
+}
+
+// Falls back to stdout when there is no path
+fn create_output(path: Option<&str>, gzip: bool) -> Box<dyn Write> {
+    let writer: Box<dyn Write> = match path {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).expect("Unable to create file"),
+        )),
+        None => Box::new(io::stdout()),
+    };
+    if gzip || path.is_some_and(|path| path.ends_with(".gz")) {
+        Box::new(GzEncoder::new(writer, Compression::default()))
+    } else {
+        writer
+    }
+}
+
+fn email_in_domains(email: &str, domains: &[String]) -> bool {
+    let domain = email.rsplit_once('@').map_or("", |(_, domain)| domain);
+    domains.iter().any(|allowed| {
+        domain == allowed
+            || domain
+                .strip_suffix(allowed.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+fn errors_json(errors: &[CrawlError]) -> serde_json::Value {
+    errors
+        .iter()
+        .map(|error| {
+            json!({
+                "url": error.url.as_str(),
+                "depth": error.depth,
+                "kind": error.kind,
+                "message": error.message,
+            })
+        })
+        .collect()
+}
+
+// Bidirectional overrides and isolates, which can make text display in a different order
+const BIDI_CONTROLS: &[char] = &[
+    '\u{200e}', '\u{200f}', '\u{202a}', '\u{202b}', '\u{202c}', '\u{202d}', '\u{202e}', '\u{2066}',
+    '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+fn is_unsafe_char(c: char) -> bool {
+    c.is_control() || BIDI_CONTROLS.contains(&c)
+}
+
+// Text output may well be read in a terminal, where escape sequences from a hostile page would
+// be acted on, so control characters are written as \u{..} escapes instead. JSON escapes them
+// itself and the HTML report is read in a browser.
+fn escape_controls(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_unsafe_char) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if is_unsafe_char(c) {
+            escaped.extend(c.escape_unicode());
+        } else {
+            escaped.push(c);
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn write_lines(path: Option<&str>, lines: &[String], description: &str, gzip: bool) {
+    let mut output = create_output(path, gzip);
+    for line in lines {
+        writeln!(output, "{}", escape_controls(line)).expect("Unable to write data");
+    }
+    drop(output);
+    if let Some(path) = path {
+        report_written(description, path, gzip);
+    }
+}
+
+// JSON and HTML documents escape what they need to themselves and are written as they are
+fn write_document(path: Option<&str>, document: &str, description: &str, gzip: bool) {
+    let mut output = create_output(path, gzip);
+    writeln!(output, "{}", document).expect("Unable to write data");
+    drop(output);
+    if let Some(path) = path {
+        report_written(description, path, gzip);
+    }
+}
+
+// Gzipped output may be going to stdout, so status messages move out of its way
+fn report_written(description: &str, path: &str, gzip: bool) {
+    if gzip {
+        eprintln!("{} have been written to '{}'", description, path);
+    } else {
+        println!("{} have been written to '{}'", description, path);
+    }
+}
+
+fn politeness_log(
+    throttles: &HashMap<String, HostThrottle>,
+    config: &CrawlConfig,
+    rate: Option<f64>,
+) -> Vec<String> {
+    let millis = |duration: Duration| duration.as_millis() as u64;
+    let mut hosts: Vec<(&String, &HostThrottle)> = throttles.iter().collect();
+    hosts.sort_by_key(|(host, _)| *host);
+    hosts
+        .into_iter()
+        .map(|(host, throttle)| {
+            let audit = &throttle.audit;
+            json!({
+                "host": host,
+                "requests": audit.requests,
+                "delay_ms": millis(config.delay),
+                "jitter_ms": millis(config.jitter),
+                "rate": rate,
+                "effective_delay_ms": millis(throttle.delay),
+                "min_gap_ms": audit.min_gap.map(millis),
+                "backoffs": audit.backoffs,
+                "retry_after_holds": audit.holds,
+                "retry_after_ms": millis(audit.held),
+                "paused": throttle.paused,
+            })
+            .to_string()
+        })
+        .collect()
+}
+
+fn graph_dot(edges: &HashSet<(Url, Url)>) -> Vec<String> {
+    let escape = |url: &Url| url.as_str().replace('\\', "\\\\").replace('"', "\\\"");
+    let mut sorted: Vec<&(Url, Url)> = edges.iter().collect();
+    sorted.sort();
+
+    let mut lines = vec!["digraph harvest {".to_string()];
+    for (from, to) in sorted {
+        lines.push(format!("    \"{}\" -> \"{}\";", escape(from), escape(to)));
+    }
+    lines.push("}".to_string());
+    lines
+}
+
+fn sorted_forms(
+    forms: &HashMap<(Url, String), HashSet<String>>,
+) -> Vec<(&Url, &String, Vec<&String>)> {
+    let mut sorted: Vec<(&Url, &String, Vec<&String>)> = forms
+        .iter()
+        .map(|((action, method), fields)| (action, method, sorted_set(fields)))
+        .collect();
+    sorted.sort();
+    sorted
+}
+
+fn sorted_technologies(technologies: &HashMap<String, String>) -> Vec<(&String, &String)> {
+    let mut sorted: Vec<(&String, &String)> = technologies.iter().collect();
+    sorted.sort();
+    sorted
+}
+
+fn sorted_sensitive(sensitive: &HashMap<Url, String>) -> Vec<(&String, &Url)> {
+    let mut sorted: Vec<(&String, &Url)> = sensitive
+        .iter()
+        .map(|(url, category)| (category, url))
+        .collect();
+    sorted.sort();
+    sorted
+}
+
+fn sorted_cookies(cookies: &HashSet<(String, cookies::Cookie)>) -> Vec<&(String, cookies::Cookie)> {
+    let mut sorted: Vec<&(String, cookies::Cookie)> = cookies.iter().collect();
+    sorted.sort();
+    sorted
+}
+
+fn cookie_line(host: &str, cookie: &cookies::Cookie) -> String {
+    let mut parts = vec![host.to_string(), cookie.name.clone()];
+    let attributes = cookie.attributes();
+    if !attributes.is_empty() {
+        parts.push(attributes);
+    }
+    let issues = cookie.issues();
+    if !issues.is_empty() {
+        parts.push(format!("({})", issues.join(", ")));
+    }
+    parts.join(" ")
+}
+
+fn sorted_header_report(
+    report: &HashMap<String, headers::HostHeaders>,
+) -> Vec<(&String, &headers::HostHeaders)> {
+    let mut sorted: Vec<(&String, &headers::HostHeaders)> = report.iter().collect();
+    sorted.sort_by_key(|(host, _)| *host);
+    sorted
+}
+
+fn technologies_json(technologies: &[(&String, &String)]) -> serde_json::Value {
+    technologies
+        .iter()
+        .map(|(name, evidence)| json!({ "name": name, "evidence": evidence }))
+        .collect()
+}
+
+struct HostBreakdown {
+    host: String,
+    fetched: usize,
+    parsed: usize,
+    words: usize,
+    emails: usize,
+}
+
+fn host_breakdown(state: &CrawlState) -> Vec<HostBreakdown> {
+    if state.hosts.is_empty() {
+        return Vec::new();
+    }
+    // Hosts whose pages were all fetched but none parsed still get a row
+    let mut hosts: Vec<String> = state
+        .links
+        .iter()
+        .map(|(url, _)| host_key(url))
+        .chain(state.hosts.keys().cloned())
+        .collect();
+    hosts.sort();
+    hosts.dedup();
+    hosts
+        .into_iter()
+        .map(|host| HostBreakdown {
+            fetched: state
+                .links
+                .iter()
+                .filter(|(url, _)| host_key(url) == host)
+                .count(),
+            parsed: state.hosts.get(&host).map_or(0, |summary| summary.parsed),
+            words: state
+                .hosts
+                .get(&host)
+                .map_or(0, |summary| summary.words.len()),
+            emails: state
+                .emails
+                .values()
+                .filter(|sources| sources.iter().any(|url| host_key(url) == host))
+                .count(),
+            host,
+        })
+        .collect()
+}
+
+fn host_breakdown_json(hosts: &[HostBreakdown]) -> serde_json::Value {
+    hosts
+        .iter()
+        .map(|host| {
+            json!({
+                "host": host.host,
+                "pages_fetched": host.fetched,
+                "pages_parsed": host.parsed,
+                "unique_words": host.words,
+                "emails": host.emails,
+            })
+        })
+        .collect()
+}
+
+// The number of words at each depth that were not found at any shallower one
+fn new_words_by_depth(depths: &[DepthWords]) -> Vec<usize> {
+    let mut seen: HashSet<&String> = HashSet::new();
+    depths
+        .iter()
+        .map(|depth| {
+            let new = depth
+                .words
+                .keys()
+                .filter(|word| !seen.contains(word))
+                .count();
+            seen.extend(depth.words.keys());
+            new
+        })
+        .collect()
+}
+
+// URLs from --skip-visited, one per line; lines from the links output are reduced to their URL
+fn load_visited(path: &str, config: &CrawlConfig) -> io::Result<HashSet<Url>> {
+    let mut visited = HashSet::new();
+    for (number, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        let link = match line
+            .split_whitespace()
+            .find(|field| field.contains("://"))
+            .or_else(|| line.split_whitespace().next())
+        {
+            Some(link) => link,
+            None => continue,
+        };
+        match Url::parse(link) {
+            Ok(url) => {
+                visited.insert(normalize_url(&url, config));
+            }
+            Err(err) => eprintln!(
+                "Warning: Ignoring line {} of '{}', '{}' is not a URL: {}",
+                number + 1,
+                path,
+                link,
+                err
+            ),
+        }
+    }
+    Ok(visited)
+}
+
+fn html_report(
+    seed: &str,
+    word_count: &HashMap<String, u32>,
+    state: &CrawlState,
+    min_count: u32,
+    sort: SortOrder,
+    scores: Option<&HashMap<String, f64>>,
+    top: Option<usize>,
+) -> String {
+    let strings = |items: Vec<&String>| items.into_iter().cloned().collect();
+    let report = report::Report {
+        seed,
+        generated: httpdate::fmt_http_date(std::time::SystemTime::now()),
+        summary: vec![
+            ("Pages fetched", state.links.len().to_string()),
+            ("Pages parsed", state.parsed_pages.to_string()),
+            ("Errors", state.errors.len().to_string()),
+            ("Unique words", word_count.len().to_string()),
+            ("Emails", state.emails.len().to_string()),
+            ("Socials", state.socials.len().to_string()),
+        ],
+        words: top_words(
+            ranked_words(word_count, min_count, sort, scores),
+            Some(top.unwrap_or(report::DEFAULT_WORDS)),
+        ),
+        lists: vec![
+            ("Emails", strings(sorted_keys(&state.emails))),
+            ("Socials", strings(sorted_keys(&state.socials))),
+            ("Phone numbers", strings(sorted_keys(&state.phones))),
+            (
+                "Technologies",
+                sorted_technologies(&state.technologies)
+                    .into_iter()
+                    .map(|(name, evidence)| format!("{} ({})", name, evidence))
+                    .collect(),
+            ),
+            ("Subdomains", strings(sorted_set(&state.subdomains))),
+            (
+                "Cookies",
+                sorted_cookies(&state.cookies)
+                    .into_iter()
+                    .map(|(host, cookie)| cookie_line(host, cookie))
+                    .collect(),
+            ),
+            (
+                "Header issues",
+                sorted_header_report(&state.header_report)
+                    .into_iter()
+                    .flat_map(|(host, headers)| {
+                        headers
+                            .issues()
+                            .into_iter()
+                            .map(move |issue| format!("{}: {}", host, issue))
+                    })
+                    .collect(),
+            ),
+            (
+                "Sensitive paths",
+                sorted_sensitive(&state.sensitive)
+                    .into_iter()
+                    .map(|(category, url)| format!("{}: {}", category, url))
+                    .collect(),
+            ),
+            (
+                "Errors",
+                state
+                    .errors
+                    .iter()
+                    .map(|error| format!("{}: {}", error.url, error.message))
+                    .collect(),
+            ),
+        ],
+        hosts: host_breakdown(state)
+            .into_iter()
+            .map(|host| {
+                [
+                    host.host,
+                    host.fetched.to_string(),
+                    host.parsed.to_string(),
+                    host.words.to_string(),
+                    host.emails.to_string(),
+                ]
+            })
+            .collect(),
+    };
+    report::render(&report)
+}
+
+// Words listed in a profile unless --top asks for another number
+const PROFILE_WORDS: usize = 20;
+
+fn profile_report(
+    url: &str,
+    word_count: &HashMap<String, u32>,
+    state: &CrawlState,
+    top_words: &[(&String, &u32)],
+    format: OutputFormat,
+) -> Vec<String> {
+    let technologies = sorted_technologies(&state.technologies);
+    let hosts = host_breakdown(state);
+
+    match format {
+        OutputFormat::Text => {
+            let mut lines = vec![
+                format!("Profile of {}", url),
+                format!(
+                    "Pages fetched: {}, parsed: {}",
+                    state.links.len(),
+                    state.parsed_pages
+                ),
+                format!(
+                    "Unique words: {}, emails: {}, socials: {}",
+                    word_count.len(),
+                    state.emails.len(),
+                    state.socials.len()
+                ),
+                String::new(),
+                "Top words:".to_string(),
+            ];
+            lines.extend(
+                top_words
+                    .iter()
+                    .map(|(word, count)| format!("  {}: {}", word, count)),
+            );
+            lines.push("Emails:".to_string());
+            lines.extend(
+                sorted_keys(&state.emails)
+                    .iter()
+                    .map(|e| format!("  {}", e)),
+            );
+            lines.push("Socials:".to_string());
+            lines.extend(
+                sorted_keys(&state.socials)
+                    .iter()
+                    .map(|s| format!("  {}", s)),
+            );
+            lines.push("Technologies:".to_string());
+            lines.extend(
+                technologies
+                    .iter()
+                    .map(|(name, evidence)| format!("  {} ({})", name, evidence)),
+            );
+            if !hosts.is_empty() {
+                lines.push("Hosts:".to_string());
+                lines.extend(hosts.iter().map(|host| {
+                    format!(
+                        "  {}: fetched {}, parsed {}, unique words {}, emails {}",
+                        host.host, host.fetched, host.parsed, host.words, host.emails
+                    )
+                }));
+            }
+            lines
+        }
+        OutputFormat::Json => {
+            let mut output = json!({
+                "url": url,
+                "counts": {
+                    "pages_fetched": state.links.len(),
+                    "pages_parsed": state.parsed_pages,
+                    "unique_words": word_count.len(),
+                    "emails": state.emails.len(),
+                    "socials": state.socials.len(),
+                },
+                "top_words": words_json(top_words, None, None),
+                "emails": sorted_keys(&state.emails),
+                "socials": sorted_keys(&state.socials),
+                "technologies": technologies_json(&technologies),
+            });
+            if !hosts.is_empty() {
+                output["hosts"] = host_breakdown_json(&hosts);
+            }
+            vec![serde_json::to_string_pretty(&output).expect("Unable to serialise profile")]
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum MinTls {
+    #[value(name = "1.2")]
+    Tls12,
+    #[value(name = "1.3")]
+    Tls13,
+}
+
+impl MinTls {
+    fn version(&self) -> tls::Version {
+        match self {
+            MinTls::Tls12 => tls::Version::TLS_1_2,
+            MinTls::Tls13 => tls::Version::TLS_1_3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Politeness {
+    /// No delay, no jitter, no user agent rotation
+    Aggressive,
+    /// 250ms between requests to a host
+    Normal,
+    /// 1s between requests to a host, plus up to 500ms of jitter
+    Polite,
+    /// 3s between requests to a host, plus up to 3s of jitter, rotating browser user agents
+    Stealth,
+}
+
+impl Politeness {
+    // Delay and jitter in milliseconds, and whether to rotate user agents
+    fn settings(&self) -> (u64, u64, bool) {
+        match self {
+            Politeness::Aggressive => (0, 0, false),
+            Politeness::Normal => (250, 0, false),
+            Politeness::Polite => (1000, 500, false),
+            Politeness::Stealth => (3000, 3000, true),
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "harvest")]
+#[command(author = "Ember Hext <github.com/EmberHext")]
+#[command(version = "1.0")]
+#[command(
+    about = "Crawl through a website for interesting words and more",
+    long_about = "Crawl through a website for interesting words, email addresses, and social media links",
+    after_help = "Exit codes: 0 crawl completed cleanly, 1 crawl completed but some pages failed, \
+                  answered with a 4xx or 5xx status or were redirected out of scope, \
+                  2 invalid arguments or configuration, 3 the seed URL could not be crawled, \
+                  answered with an error status or was redirected out of scope, or the login failed"
+)]
+struct Cli {
+    /// Link to page to search
+    #[arg(required_unless_present = "stdin")]
+    url: Option<String>,
+    /// File to output wordlist into; with more than one --format, give one --file per format in
+    /// the same order
+    #[arg(short, long = "file", value_name = "FILE")]
+    wlfile: Vec<String>,
+    /// Do not output a wordlist
+    #[arg(short, long)]
+    nowords: bool,
+    /// Find all emails
+    #[arg(short, long)]
+    email: bool,
+    /// File to output emails into
+    #[arg(long, value_name = "FILE")]
+    emfile: Option<String>,
+    /// Find all socials
+    #[arg(short, long)]
+    social: bool,
+    /// File to output socials into
+    #[arg(long, value_name = "FILE")]
+    socfile: Option<String>,
+    /// Find phone numbers in tel and sms links
+    #[arg(long)]
+    phone: bool,
+    /// File to output phone numbers into
+    #[arg(long, value_name = "FILE")]
+    phonefile: Option<String>,
+    /// Number of link hops to follow from the seed page, default is 2; 0 fetches the seed page only
+    #[arg(short, long, value_name = "x")]
+    depth: Option<u8>,
+    /// Minimum word length, default is 4; Chinese and Japanese characters count as two
+    #[arg(short, long, value_name = "x")]
+    min: Option<u8>,
+    /// The number of most common words to filter, default is 400, max is 1000; matching ignores case
+    #[arg(short, long, value_name = "x")]
+    common: Option<u16>,
+    /// Allow the crawler to follow external links
+    #[arg(short, long)]
+    offsite: bool,
+    /// Follow links from the seed domain to external pages, but not links from those pages to other external pages
+    #[arg(long, conflicts_with = "offsite")]
+    offsite_once: bool,
+    /// User agent to send in http header
+    #[arg(short, long, value_name = "AGENT")]
+    agent: Option<String>,
+    /// Coverty all words to lowercase
+    #[arg(short, long)]
+    lower: bool,
+    /// Parses words that contains diacritics, but removes the diacritics
+    #[arg(short = 'r', long)]
+    diacrit_remove: bool,
+    /// Comma separated HTTP status codes whose bodies are parsed, default is any 2xx
+    #[arg(long, value_name = "CODES", value_delimiter = ',')]
+    parse_status: Vec<u16>,
+    /// File to output crawled links and their status codes into
+    #[arg(long, value_name = "FILE")]
+    linkfile: Option<String>,
+    /// Send a HEAD request first and skip pages that are not HTML or are too large
+    #[arg(long)]
+    preflight: bool,
+    /// Maximum response body size in bytes to download and parse
+    #[arg(long, value_name = "BYTES")]
+    max_body_size: Option<u64>,
+    /// Directory for caching pages between runs; unchanged pages are revalidated instead of re-downloaded
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<String>,
+    /// Format of the output file; repeat it to write several formats from one crawl, each to its
+    /// own --file or under --output-dir. Text also writes the separate email, social and other
+    /// lists, json puts them in the one file
+    #[arg(long, value_enum, default_values_t = [OutputFormat::Text])]
+    format: Vec<OutputFormat>,
+    /// Also group the words found by the page they came from
+    #[arg(long)]
+    by_page: bool,
+    /// Find all Bitcoin, Ethereum and Monero addresses
+    #[arg(long)]
+    crypto: bool,
+    /// File to output cryptocurrency addresses into
+    #[arg(long, value_name = "FILE")]
+    cryptofile: Option<String>,
+    /// Honour <meta name="robots">: don't follow links from nofollow pages or count words on noindex pages
+    #[arg(long)]
+    respect_meta_robots: bool,
+    /// Warn when fewer than this many pages were successfully parsed
+    #[arg(long, value_name = "N")]
+    warn_thin: Option<usize>,
+    /// Order of the words in the output
+    #[arg(long, value_enum, default_value_t = SortOrder::Count)]
+    sort: SortOrder,
+    /// Minimum TLS version to accept. Connections use native-tls (OpenSSL on Linux),
+    /// except with 1.3 which uses the bundled rustls backend
+    #[arg(long, value_enum, value_name = "VERSION")]
+    min_tls: Option<MinTls>,
+    /// Print a recon profile of the site (top words, contacts, technologies) instead of a wordlist;
+    /// it lists the top 20 words, or --top, ranked as the wordlist would be
+    #[arg(long)]
+    profile: bool,
+    /// Detect the server stack and CMS from headers, cookies and markup
+    #[arg(long)]
+    tech: bool,
+    /// File to output detected technologies into
+    #[arg(long, value_name = "FILE")]
+    techfile: Option<String>,
+    /// File of extra technology signatures, one "Technology | header:<name>, cookie or path | pattern" per line
+    #[arg(long, value_name = "FILE")]
+    tech_signatures: Option<String>,
+    /// Flag discovered links to admin, login, backup, config, API and other interesting paths,
+    /// listed by category
+    #[arg(long)]
+    flag_sensitive: bool,
+    /// File to output flagged links into
+    #[arg(long, value_name = "FILE")]
+    sensitivefile: Option<String>,
+    /// File of extra patterns for --flag-sensitive, one "Category | path substring" per line
+    #[arg(long, value_name = "FILE")]
+    sensitive_patterns: Option<String>,
+    /// Find all forms, with their action URL, method and field names
+    #[arg(long)]
+    forms: bool,
+    /// File to output forms into
+    #[arg(long, value_name = "FILE")]
+    formfile: Option<String>,
+    /// Also crawl the action URLs of GET forms
+    #[arg(long)]
+    follow_forms: bool,
+    /// Collect query string parameter and form field names, with foo[0] and foo[] both written as foo[]
+    #[arg(long)]
+    params: bool,
+    /// File to output parameter names into
+    #[arg(long, value_name = "FILE")]
+    paramfile: Option<String>,
+    /// List the cookies each host sets, with their attributes and the security flags they lack
+    #[arg(long)]
+    report_cookies: bool,
+    /// File to output cookies into
+    #[arg(long, value_name = "FILE")]
+    cookiefile: Option<String>,
+    /// Summarise the Server, X-Powered-By and security headers each host sends, such as
+    /// Content-Security-Policy, Strict-Transport-Security and X-Frame-Options, and flag the
+    /// security headers it leaves out
+    #[arg(long)]
+    report_headers: bool,
+    /// File to output the header summary into
+    #[arg(long, value_name = "FILE")]
+    headerfile: Option<String>,
+    /// Slow down, and eventually stop, requests to a host when most recent responses are 403, 429 or 503
+    #[arg(long)]
+    backoff_on_block: bool,
+    /// File to write the crawled link graph into, in GraphViz DOT format
+    #[arg(long, value_name = "FILE")]
+    graph: Option<String>,
+    /// Include links to offsite pages that were not crawled in the graph
+    #[arg(long)]
+    graph_offsite: bool,
+    /// Only accept words made of the letters a-z, splitting on whitespace as older versions did
+    #[arg(long)]
+    ascii_only: bool,
+    /// Comma separated query parameters to remove from URLs before crawling, names ending in * match
+    /// prefixes. Without a list, common tracking and session parameters (utm_*, fbclid, PHPSESSID...) are removed
+    #[arg(long, value_name = "NAMES", value_delimiter = ',', num_args = 0..)]
+    strip_params: Option<Vec<String>>,
+    /// Extract names, emails, phone numbers and social links from JSON-LD structured data, and phone numbers from tel and sms links
+    #[arg(long)]
+    jsonld: bool,
+    /// Also output the raw JSON-LD blocks found
+    #[arg(long, requires = "jsonld")]
+    jsonld_raw: bool,
+    /// File to output JSON-LD names and phone numbers into
+    #[arg(long, value_name = "FILE")]
+    jsonldfile: Option<String>,
+    /// Minimum delay in milliseconds between requests to the same host
+    #[arg(long, value_name = "MS")]
+    delay: Option<u64>,
+    /// Maximum random extra delay in milliseconds added to each wait
+    #[arg(long, value_name = "MS")]
+    jitter: Option<u64>,
+    /// Send a different common browser user agent with each request, unless --agent is given
+    #[arg(long)]
+    rotate_agent: bool,
+    /// Preset for delay, jitter and user agent rotation; --delay, --jitter and --rotate-agent override it
+    #[arg(long, value_enum, value_name = "PRESET")]
+    politeness: Option<Politeness>,
+    /// Do not decode HTML entities left in page text after parsing, such as &amp;#64;
+    #[arg(long)]
+    no_entity_decode: bool,
+    /// Percent-decode page text before extracting words and contacts, e.g. name%40example.com
+    #[arg(long)]
+    percent_decode: bool,
+    /// Merge plurals and words within this many edits of a more frequent word into it, 0 merges plurals only
+    #[arg(long, value_name = "EDITS")]
+    dedup_near: Option<usize>,
+    /// Login page whose form is submitted before crawling, keeping the session cookies for the crawl
+    #[arg(long, value_name = "URL")]
+    login_url: Option<String>,
+    /// Field to fill in on the login form, e.g. "username=admin"; can be given more than once
+    #[arg(long, value_name = "NAME=VALUE", requires = "login_url")]
+    login_field: Vec<String>,
+    /// Load HTML pages in a headless browser and extract from the DOM after JavaScript has run
+    #[cfg(feature = "render")]
+    #[arg(long)]
+    render: bool,
+    /// Chromium-based browser binary used by --render; by default the usual install locations
+    /// are searched
+    #[cfg(feature = "render")]
+    #[arg(long, value_name = "PATH", requires = "render")]
+    browser: Option<String>,
+    /// Time in milliseconds pages are given to run their scripts with --render
+    #[cfg(feature = "render")]
+    #[arg(long, value_name = "MS", default_value_t = 5000)]
+    render_budget: u64,
+    /// Show a live dashboard of the crawl instead of the usual progress output; q stops the crawl
+    /// and writes the results found so far
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    tui: bool,
+    /// How words are ranked in the output; prominence also weighs titles, headings and position,
+    /// tfidf favours words that are rare in general English
+    #[arg(long, value_enum, default_value_t = Rank::Count, conflicts_with = "sort")]
+    rank: Rank,
+    /// Number of link hops to follow to pages outside the seed domain; the lower of this and --depth applies.
+    /// Unlike --offsite-once this counts hops from the seed, so external pages may still link on to other sites
+    #[arg(long, value_name = "x", requires = "offsite")]
+    offsite_depth: Option<u8>,
+    /// Look up the MX records of each email domain and flag addresses that cannot receive mail;
+    /// refused with --tor and socks5h:// proxies, as the lookups would go to local DNS
+    #[arg(long, requires = "email")]
+    verify_mx: bool,
+    /// Gzip every output, including output to stdout; files ending in .gz are always compressed
+    #[arg(long)]
+    gzip_output: bool,
+    /// Unicode normalization form applied to page text; the NFK forms fold compatibility characters
+    #[arg(long, value_enum, value_name = "FORM", default_value_t = NormalizationForm::Nfc)]
+    normalize_form: NormalizationForm,
+    /// Regex whose matches are taken as the words, or its first capture group if it has one
+    #[arg(long, value_name = "REGEX", conflicts_with = "ascii_only")]
+    word_regex: Option<String>,
+    /// Remove apostrophes from words, so "don't" is counted as "dont"; by default they are kept
+    #[arg(long, conflicts_with_all = ["word_regex", "split_contractions"])]
+    strip_apostrophes: bool,
+    /// Take contractions apart: split "don't" into "do" and "nt", or with expand into "do" and
+    /// "not"; other words with apostrophes, such as "o'brien", are kept whole
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_missing_value = "split",
+        conflicts_with = "word_regex"
+    )]
+    split_contractions: Option<Contractions>,
+    /// Count the number of pages each word appears on instead of its total number of occurrences
+    #[arg(long)]
+    document_frequency: bool,
+    /// Also count the words of the meta description and the Open Graph title and description
+    #[arg(long)]
+    meta_words: bool,
+    /// Number of body occurrences each word of a meta description or Open Graph title counts as
+    /// with --meta-words; it adds to the word's body count on the same page
+    #[arg(long, value_name = "N", default_value_t = 3, requires = "meta_words")]
+    meta_weight: u32,
+    /// Only speak HTTP/1.1, for servers that fail with protocol errors when HTTP/2 is negotiated
+    #[arg(long)]
+    http1_only: bool,
+    /// Skip words in elements hidden by the hidden attribute, aria-hidden or an inline display:none
+    /// or visibility:hidden style; stylesheets and scripts are not evaluated
+    #[arg(long)]
+    exclude_hidden: bool,
+    /// Comma separated attributes also holding links to follow, e.g. "data-href,data-url"
+    #[arg(long, value_name = "ATTRS", value_delimiter = ',')]
+    data_attrs: Vec<String>,
+    /// Comma separated domains to keep emails from, including their subdomains; others are dropped
+    #[arg(
+        long,
+        value_name = "DOMAINS",
+        value_delimiter = ',',
+        requires = "email"
+    )]
+    email_domains: Vec<String>,
+    /// Only keep emails at the seed's registrable domain, e.g. example.co.uk for www.example.co.uk,
+    /// in addition to any --email-domains
+    #[arg(long, requires = "email")]
+    only_target_emails: bool,
+    /// File to output pages that failed into, with their depth, kind of error and message
+    #[arg(long, value_name = "FILE")]
+    errorfile: Option<String>,
+    /// Directory to save every parsed page into, for re-running extraction later with --replay
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    dump_dir: Option<String>,
+    /// Re-run extraction over pages saved with --dump-dir instead of fetching them; pages that
+    /// were not saved are skipped. Nothing is sent over the network, so options that would need
+    /// to are refused
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["login_url", "image_exif", "verify_mx"]
+    )]
+    replay: Option<String>,
+    /// Only output words found on at least this many distinct pages, however often they occur
+    #[arg(long, value_name = "N")]
+    min_pages: Option<u32>,
+    /// List up to K of the pages each word was found on with it, in JSON output, to show where an
+    /// unexpected word came from
+    #[arg(long, value_name = "K", conflicts_with = "approx_counts")]
+    word_sources: Option<usize>,
+    /// Proxy to send every request through, e.g. "http://127.0.0.1:8080" or "socks5h://127.0.0.1:1080";
+    /// socks5h resolves host names on the proxy side, which reaches internal names such as intranet.corp
+    #[arg(long, value_name = "URL", conflicts_with = "tor")]
+    proxy: Option<String>,
+    /// Send every request through the local Tor SOCKS proxy at 127.0.0.1:9050, resolving names
+    /// (including .onion addresses) on the Tor side
+    #[arg(long)]
+    tor: bool,
+    /// Header whose value is filled in again for every page, e.g. "X-Request-ID: {{uuid}}"; the
+    /// tokens are {{random}} (16 random hex digits), {{uuid}} (a random UUID) and {{seed-host}}
+    #[arg(long, value_name = "HEADER")]
+    header_template: Vec<String>,
+    /// Skip extraction from pages whose <link rel="canonical"> URL was already extracted; a page
+    /// without one stands for its own URL
+    #[arg(long)]
+    canonical_dedup: bool,
+    /// SQLite database to store per-page word counts, emails, socials and links in; an existing
+    /// database is updated
+    #[arg(long, value_name = "FILE")]
+    sqlite: Option<String>,
+    /// Pause a host for as long as its Retry-After header asks on 429 and 503 responses, then retry the page once
+    #[arg(long)]
+    respect_retry_after: bool,
+    /// File to output the top 100 words, or --top words, into with sizes from 1 to 100 for word cloud tools, as JSON when it ends in .json and as word,size lines otherwise
+    #[arg(long, value_name = "FILE")]
+    wordcloud: Option<String>,
+    /// Only output the N highest ranked words, overall and for each page or depth with --by-page or --by-depth
+    #[arg(long, value_name = "N")]
+    top: Option<usize>,
+    /// Follow rel="next" and rel="prev" pagination links for up to N pages from any crawled page, even past --depth
+    #[arg(long, value_name = "N")]
+    max_pagination: Option<u32>,
+    /// Treat a domain and its www. subdomain as the same site, so example.com and www.example.com are both crawled without --offsite
+    #[arg(long)]
+    fuzzy_host: bool,
+    /// Keep #! fragments (hashbang routes of older single page apps) so each one is crawled as its own page; ordinary # fragments are still stripped
+    #[arg(long)]
+    treat_hashbang_as_page: bool,
+    /// Find all subdomains of the seed's registrable domain in links, sources, scripts and structured data
+    #[arg(long)]
+    subdomains: bool,
+    /// File to output subdomains into
+    #[arg(long, value_name = "FILE")]
+    subdomainfile: Option<String>,
+    /// Maximum number of requests per second across all hosts, allowing short bursts; when --delay is also set both apply, so the stricter one wins
+    #[arg(long, value_name = "N")]
+    rate: Option<f64>,
+    /// Find all image URLs from img src and srcset attributes
+    #[arg(long)]
+    images: bool,
+    /// File to output images into
+    #[arg(long, value_name = "FILE")]
+    imagefile: Option<String>,
+    /// Download found images and read their EXIF metadata (camera, author, date, GPS position); implies --images
+    #[arg(long)]
+    image_exif: bool,
+    /// Also retry requests that are not idempotent, such as a POST login form, after a Retry-After pause; off by default because the repeated request can repeat its side effects
+    #[arg(long)]
+    retry_non_idempotent: bool,
+    /// Extract words, emails and the rest from linked PDF documents as well, within --max-body-size
+    #[arg(long)]
+    parse_pdf: bool,
+    /// Skip extracting pages whose SimHash fingerprint differs from an already extracted page's in at most this many of 64 bits, around 3 catches templated near duplicates
+    #[arg(long, value_name = "BITS")]
+    skip_similar: Option<u32>,
+    /// File to write a per-host audit of request pacing into as JSON lines: delays configured and applied, the shortest gap between requests, and block backoffs and Retry-After holds
+    #[arg(long, value_name = "FILE")]
+    politeness_log: Option<String>,
+    /// Minimum fraction of letters a word must be made of, from 0 to 1, default is 0.6; mainly filters --word-regex matches such as a1b2c3d4
+    #[arg(long, value_name = "RATIO")]
+    min_alpha_ratio: Option<f64>,
+    /// Directory to write every enabled output into under conventional names (words.txt, emails.txt, links.txt, errors.txt and so on) along with a manifest.json of the files written; file options still override single outputs
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<String>,
+    /// Count words in bounded memory, keeping only the N most frequent words with counts estimated
+    /// by a Count-Min Sketch; counts may come out slightly high and rare words may be missed
+    #[arg(long, value_name = "N", conflicts_with_all = ["by_page", "by_depth", "min_pages"])]
+    approx_counts: Option<usize>,
+    /// Standalone HTML report of the crawl to write, with the top words, contacts, technologies,
+    /// per host statistics and errors; --top sets the number of words, default 50
+    #[arg(long, value_name = "FILE")]
+    report: Option<String>,
+    /// Accept certificates issued for a different host name, for virtual hosts reached by IP
+    /// address or another name. The certificate chain is still verified, but any site holding a
+    /// valid certificate for any name can then impersonate the target, so only use it for hosts
+    /// whose address is known to be right. Not available with --min-tls 1.3
+    #[arg(long)]
+    accept_hostname_mismatch: bool,
+    /// Follow the RSS, Atom and JSON feeds pages link to and extract words and author emails from
+    /// their entries, leaving out entries whose page was already crawled
+    #[arg(long)]
+    feeds: bool,
+    /// Skip pages that redirect outside the crawl scope, to a host other than the one requested
+    /// and the seed's. By default redirects are followed anywhere and the page they end on is
+    /// extracted; skipped pages are listed in the errors output
+    #[arg(long, conflicts_with = "offsite")]
+    strict_scope: bool,
+    /// Upgrade http links to https so a site served over both is crawled once. Hosts that do not
+    /// answer over https are fetched over http instead for the rest of the crawl, after a warning.
+    /// Without it, links are still upgraded for hosts that sent Strict-Transport-Security over
+    /// https earlier in the same crawl, as browsers do; nothing is remembered between runs
+    #[arg(long)]
+    prefer_https: bool,
+    /// Also group the words found by crawl depth, with the number of pages, unique words and
+    /// words not found at any shallower depth for each
+    #[arg(long)]
+    by_depth: bool,
+    /// File of URLs handled by an earlier run to skip, one per line; the links output of a run
+    /// can be given as it is. Skipped pages are not fetched, so their links are not followed
+    /// either. The seed is always crawled
+    #[arg(long, value_name = "FILE")]
+    skip_visited: Option<String>,
+    /// Skip extraction from pages whose body is smaller than this many bytes, such as placeholders
+    /// and empty redirect pages; they are still listed in the links output. Off by default, as
+    /// legitimately terse pages are skipped too
+    #[arg(long, value_name = "BYTES")]
+    min_content_length: Option<u64>,
+    /// Follow the links of each page in random order instead of the order they appear in, which
+    /// spreads requests across the sections of a site at the cost of crawling related pages together
+    #[arg(long)]
+    shuffle_links: bool,
+    /// Seed for --shuffle-links, so a shuffled crawl can be repeated in the same order
+    #[arg(long, value_name = "N", requires = "shuffle_links")]
+    shuffle_seed: Option<u64>,
+    /// Also find emails written as "name (at) example (dot) com", "name[at]example[dot]com",
+    /// "name at example dot com" and similar; off by default, as the looser patterns can match text that is not an address
+    #[arg(long)]
+    deobfuscate_emails: bool,
+    /// Follow at most this many links to pages not crawled yet from any one page, the first ones
+    /// in document order; keeps pages such as HTML sitemaps and tag clouds from swamping the crawl
+    #[arg(long, value_name = "N")]
+    max_links_per_page: Option<usize>,
+    /// Extra root certificate to trust, such as the CA of a TLS inspecting proxy, in PEM
+    /// (-----BEGIN CERTIFICATE-----) or binary DER form, one certificate per file; may be given
+    /// more than once. The system roots are still trusted and verification stays on
+    #[arg(long, value_name = "FILE")]
+    ca_cert: Vec<String>,
+    /// Look each host up only once during the crawl instead of for every request
+    #[arg(long)]
+    dns_cache: bool,
+    /// Name server to look hosts up with instead of the system's, as IP or IP:PORT; implies
+    /// --dns-cache. Behind --proxy or --tor only the proxy's own host is looked up
+    #[arg(long, value_name = "IP")]
+    resolver: Option<String>,
+    /// Connect to IP whenever HOST is requested, without looking it up, e.g.
+    /// "www.example.com:10.0.0.5" to crawl one backend behind a load balancer; the Host header,
+    /// TLS names and ports stay those of the URL. May be given more than once
+    #[arg(long, value_name = "HOST:IP")]
+    host_override: Vec<String>,
+    /// Also write password candidates made from the wordlist, such as Word, word123, word! and
+    /// w0rd, to a separate output
+    #[arg(long)]
+    mangle: bool,
+    /// File to output password candidates into
+    #[arg(long, value_name = "FILE")]
+    manglefile: Option<String>,
+    /// File of hashcat style rules to make the candidates with instead of the built-in ones, one
+    /// rule per line; the functions : l u c C t r d [ ] $X ^X and sXY are supported
+    #[arg(long, value_name = "FILE", requires = "mangle")]
+    mangle_rules: Option<String>,
+    /// Read one HTML page from standard input and extract from it instead of crawling; its links
+    /// are recorded but nothing is fetched, so crawl flags such as --depth and --offsite make no
+    /// difference
+    #[arg(
+        long,
+        requires = "base_url",
+        conflicts_with_all = ["url", "replay", "login_url", "image_exif"]
+    )]
+    stdin: bool,
+    /// URL the page read with --stdin was served from, which its relative links are resolved against
+    #[arg(long, value_name = "URL", requires = "stdin", conflicts_with = "url")]
+    base_url: Option<String>,
+    /// Print how long the crawl spent setting up clients, waiting between requests, on requests up
+    /// to the first byte of the response (DNS, connect and TLS included), downloading bodies,
+    /// parsing and extracting, and on anything else
+    #[arg(long)]
+    benchmark: bool,
+}
+
+// Outputs written by the run, as (name, path) pairs, for the --output-dir manifest
+fn output_paths(cli: &Cli) -> Vec<(&'static str, String)> {
+    let words = cli.wlfile.iter().map(|path| ("words", path.clone()));
+    let others = [
+        ("emails", &cli.emfile),
+        ("socials", &cli.socfile),
+        ("phones", &cli.phonefile),
+        ("crypto", &cli.cryptofile),
+        ("technologies", &cli.techfile),
+        ("forms", &cli.formfile),
+        ("params", &cli.paramfile),
+        ("cookies", &cli.cookiefile),
+        ("headers", &cli.headerfile),
+        ("sensitive", &cli.sensitivefile),
+        ("jsonld", &cli.jsonldfile),
+        ("subdomains", &cli.subdomainfile),
+        ("images", &cli.imagefile),
+        ("links", &cli.linkfile),
+        ("errors", &cli.errorfile),
+        ("graph", &cli.graph),
+        ("wordcloud", &cli.wordcloud),
+        ("mangled", &cli.manglefile),
+        ("sqlite", &cli.sqlite),
+        ("politeness", &cli.politeness_log),
+        ("report", &cli.report),
+    ]
+    .into_iter()
+    .filter_map(|(name, path)| Some((name, path.clone()?)));
+    words.chain(others).collect()
+}
+
+// Outputs without a path of their own get a conventional name inside the output directory
+fn fill_output_dir(cli: &mut Cli, dir: &Path) {
+    let text = cli.format.contains(&OutputFormat::Text) && !cli.profile;
+    let gzip = if cli.gzip_output { ".gz" } else { "" };
+    if cli.wlfile.is_empty() {
+        for format in &cli.format {
+            let words = match (cli.profile, format) {
+                (true, OutputFormat::Text) => "profile.txt",
+                (true, OutputFormat::Json) => "profile.json",
+                (false, OutputFormat::Text) => "words.txt",
+                (false, OutputFormat::Json) => "words.json",
+            };
+            cli.wlfile
+                .push(format!("{}{}", dir.join(words).display(), gzip));
+        }
+    }
+    let enabled = [
+        (text && (cli.email || cli.jsonld), "emails.txt"),
+        (text && (cli.social || cli.jsonld), "socials.txt"),
+        (text && cli.phone, "phones.txt"),
+        (text && cli.crypto, "crypto.txt"),
+        (text && cli.tech, "tech.txt"),
+        (text && cli.forms, "forms.txt"),
+        (text && cli.params, "params.txt"),
+        (text && cli.report_cookies, "cookies.txt"),
+        (text && cli.report_headers, "headers.txt"),
+        (text && cli.flag_sensitive, "sensitive.txt"),
+        (text && cli.jsonld, "jsonld.txt"),
+        (text && cli.subdomains, "subdomains.txt"),
+        (text && (cli.images || cli.image_exif), "images.txt"),
+        (true, "links.txt"),
+        (true, "errors.txt"),
+        (cli.mangle, "mangled.txt"),
+    ];
+    let paths = [
+        &mut cli.emfile,
+        &mut cli.socfile,
+        &mut cli.phonefile,
+        &mut cli.cryptofile,
+        &mut cli.techfile,
+        &mut cli.formfile,
+        &mut cli.paramfile,
+        &mut cli.cookiefile,
+        &mut cli.headerfile,
+        &mut cli.sensitivefile,
+        &mut cli.jsonldfile,
+        &mut cli.subdomainfile,
+        &mut cli.imagefile,
+        &mut cli.linkfile,
+        &mut cli.errorfile,
+        &mut cli.manglefile,
+    ];
+    for (path, (enabled, name)) in paths.into_iter().zip(enabled) {
+        if enabled && path.is_none() {
+            *path = Some(format!("{}{}", dir.join(name).display(), gzip));
+        }
+    }
+}
+
+fn write_manifest(dir: &Path, seed: &str, outputs: &[(&str, String)], gzip: bool) {
+    let files: Vec<serde_json::Value> = outputs
+        .iter()
+        .filter_map(|(name, path)| {
+            let bytes = fs::metadata(path).ok()?.len();
+            Some(json!({ "output": name, "path": path, "bytes": bytes }))
+        })
+        .collect();
+    let manifest = json!({ "seed": seed, "files": files });
+    let path = dir.join("manifest.json");
+    match fs::write(&path, format!("{:#}\n", manifest)) {
+        Ok(()) => report_written("Manifest entries", &path.display().to_string(), gzip),
+        Err(err) => eprintln!("Error: Unable to write '{}': {}", path.display(), err),
+    }
+}
+
+fn main() {
+    let mut cli = Cli::parse();
+
+    if let Some(dir) = cli.output_dir.clone() {
+        if let Err(err) = fs::create_dir_all(&dir) {
+            eprintln!(
+                "Error: Unable to create output directory '{}': {}",
+                dir, err
+            );
+            std::process::exit(EXIT_CONFIG_ERROR);
+        }
+        fill_output_dir(&mut cli, Path::new(&dir));
+    }
+    let outputs = output_paths(&cli);
+
+    // With --stdin the base URL stands in for the seed, clap makes sure one of them is given
+    let seed = cli
+        .url
+        .clone()
+        .or_else(|| cli.base_url.clone())
+        .unwrap_or_default();
+    let seed_url = Url::parse(&seed).unwrap_or_else(|err| {
+        eprintln!("Error: Invalid URL '{}': {}", seed, err);
+        std::process::exit(EXIT_CONFIG_ERROR);
+    });
+    for (i, format) in cli.format.iter().enumerate() {
+        if cli.format[..i].contains(format) {
+            eprintln!("Error: Each --format can only be given once");
+            std::process::exit(EXIT_CONFIG_ERROR);
+        }
+    }
+    // Formats are paired with --file options in the order given, a single format can do without
+    let word_files: Vec<Option<&str>> = match cli.wlfile.len() {
+        0 if cli.format.len() == 1 => vec![None],
+        n if n == cli.format.len() => cli.wlfile.iter().map(|path| Some(path.as_str())).collect(),
+        _ => {
+            eprintln!(
+                "Error: Give one --file for each --format, in the same order, or use --output-dir"
+            );
+            std::process::exit(EXIT_CONFIG_ERROR);
+        }
+    };
+    for (i, path) in cli.wlfile.iter().enumerate() {
+        if cli.wlfile[..i].contains(path) {
+            eprintln!(
+                "Error: Each --format needs a different --file, '{}' is given twice",
+                path
+            );
+            std::process::exit(EXIT_CONFIG_ERROR);
+        }
+    }
+    let min_count = 4;
+    let headers =
+        headers_from_strings(&["Accept-Charset: iso-8859-5, Unicode-1-1; q = 0,8".to_string()])
+            .unwrap_or_else(|err| {
+                eprintln!("Error: {}", err);
+                std::process::exit(EXIT_CONFIG_ERROR);
+            });
+
+    let cache = cli.cache_dir.as_ref().map(|dir| {
+        Cache::open(Path::new(dir)).unwrap_or_else(|err| {
+            eprintln!("Error: Unable to open cache directory '{}': {}", dir, err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        })
+    });
+
+    let ca_certs: Vec<Certificate> = cli
+        .ca_cert
+        .iter()
+        .map(|path| {
+            let contents = fs::read(path).unwrap_or_else(|err| {
+                eprintln!("Error: Unable to read '{}': {}", path, err);
+                std::process::exit(EXIT_CONFIG_ERROR);
+            });
+            let certificate = if contents.starts_with(b"-----BEGIN") {
+                Certificate::from_pem(&contents)
+            } else {
+                Certificate::from_der(&contents)
+            };
+            certificate.unwrap_or_else(|err| {
+                eprintln!("Error: '{}' is not a PEM or DER certificate: {}", path, err);
+                std::process::exit(EXIT_CONFIG_ERROR);
+            })
+        })
+        .collect();
+
+    let host_overrides: Vec<(String, IpAddr)> = cli
+        .host_override
+        .iter()
+        .map(|host_override| {
+            dns::parse_override(host_override).unwrap_or_else(|err| {
+                eprintln!("Error: Invalid host override '{}': {}", host_override, err);
+                std::process::exit(EXIT_CONFIG_ERROR);
+            })
+        })
+        .collect();
+    let name_server = cli.resolver.as_deref().map(|name_server| {
+        dns::parse_name_server(name_server).unwrap_or_else(|err| {
+            eprintln!("Error: Invalid resolver: {}", err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        })
+    });
+
+    if cli.word_sources.is_some() && !cli.format.contains(&OutputFormat::Json) {
+        eprintln!("Error: --word-sources needs --format json");
+        std::process::exit(EXIT_CONFIG_ERROR);
+    }
+
+    // rustls has no switch for skipping only the host name check
+    if cli.accept_hostname_mismatch && cli.min_tls == Some(MinTls::Tls13) {
+        eprintln!("Error: --accept-hostname-mismatch cannot be used with --min-tls 1.3");
+        std::process::exit(EXIT_CONFIG_ERROR);
+    }
+
+    // socks5h rather than socks5, so host names are resolved by Tor and never by the local resolver
+    let proxy_url = if cli.tor {
+        Some(TOR_PROXY.to_string())
+    } else {
+        cli.proxy.clone()
+    };
+    let proxy = proxy_url.as_ref().map(|proxy_url| {
+        Proxy::all(proxy_url).unwrap_or_else(|err| {
+            eprintln!("Error: Invalid proxy '{}': {}", proxy_url, err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        })
+    });
+    let resolver =
+        (cli.dns_cache || name_server.is_some() || !host_overrides.is_empty()).then(|| {
+            let proxy = proxy_url
+                .as_deref()
+                .and_then(|proxy_url| Url::parse(proxy_url).ok());
+            dns::HostResolver::new(host_overrides, name_server, cli.dns_cache, proxy)
+                .unwrap_or_else(|err| {
+                    eprintln!("Error: Unable to set up DNS resolver: {}", err);
+                    std::process::exit(EXIT_CONFIG_ERROR);
+                })
+        });
+    let resolves_remotely = proxy_url
+        .as_deref()
+        .is_some_and(|proxy_url| proxy_url.starts_with("socks5h://"));
+    // MX lookups cannot go through the proxy, they would hand every email domain to local DNS
+    if cli.verify_mx && resolves_remotely {
+        eprintln!(
+            "Error: --verify-mx looks domains up through local DNS, which --tor and socks5h:// proxies keep out of"
+        );
+        std::process::exit(EXIT_CONFIG_ERROR);
+    }
+    if seed_url
+        .host_str()
+        .is_some_and(|host| host.ends_with(".onion"))
+        && !resolves_remotely
+    {
+        eprintln!(
+            "Warning: .onion addresses can only be resolved through Tor, use --tor or a socks5h:// --proxy"
+        );
+    }
+
+    let header_templates =
+        header_templates_from_strings(&cli.header_template).unwrap_or_else(|err| {
+            eprintln!("Error: {}", err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        });
+
+    let dump = cli.dump_dir.as_ref().map(|dir| {
+        Corpus::create(Path::new(dir)).unwrap_or_else(|err| {
+            eprintln!("Error: Unable to create dump directory '{}': {}", dir, err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        })
+    });
+    let replay = cli.replay.as_ref().map(|dir| {
+        Corpus::open(Path::new(dir)).unwrap_or_else(|err| {
+            eprintln!("Error: Unable to read corpus in '{}': {}", dir, err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        })
+    });
+    let fetcher: Box<dyn Fetcher> = match replay {
+        _ if cli.stdin => Box::new(StdinFetcher),
+        Some(corpus) => Box::new(ReplayFetcher(corpus)),
+        #[cfg(feature = "render")]
+        None if cli.render => {
+            let browser = render::Browser::launch(
+                cli.browser.as_deref(),
+                cli.render_budget,
+                proxy_url.as_deref(),
+            )
+            .unwrap_or_else(|err| {
+                eprintln!("Error: Unable to start the browser: {}", err);
+                std::process::exit(EXIT_CONFIG_ERROR);
+            });
+            Box::new(BrowserFetcher(browser))
+        }
+        None => Box::new(HttpFetcher),
+    };
+
+    let tech = (cli.tech || cli.profile).then(|| {
+        let mut signatures = tech::builtin_signatures();
+        if let Some(ref path) = cli.tech_signatures {
+            signatures.extend(tech::load_signatures(path).unwrap_or_else(|err| {
+                eprintln!("Error: Unable to load signatures from '{}': {}", path, err);
+                std::process::exit(EXIT_CONFIG_ERROR);
+            }));
+        }
+        signatures
+    });
+
+    let mangle_rules = cli.mangle.then(|| match cli.mangle_rules {
+        Some(ref path) => mangle::load_rules(path).unwrap_or_else(|err| {
+            eprintln!("Error: Unable to load rules from '{}': {}", path, err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        }),
+        None => mangle::builtin_rules(),
+    });
+
+    let sensitive = cli.flag_sensitive.then(|| {
+        let mut patterns = sensitive::builtin_patterns();
+        if let Some(ref path) = cli.sensitive_patterns {
+            patterns.extend(sensitive::load_patterns(path).unwrap_or_else(|err| {
+                eprintln!("Error: Unable to load patterns from '{}': {}", path, err);
+                std::process::exit(EXIT_CONFIG_ERROR);
+            }));
+        }
+        patterns
+    });
+
+    let word_regex = cli.word_regex.as_ref().map(|pattern| {
+        Regex::new(pattern).unwrap_or_else(|err| {
+            eprintln!("Error: Invalid word regex: {}", err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        })
+    });
+
+    let subdomains = cli
+        .subdomains
+        .then(|| match seed_url.host_str().and_then(psl::domain_str) {
+            Some(domain) => SubdomainMatcher::new(domain),
+            None => {
+                eprintln!("Error: The seed URL has no registrable domain to find subdomains of");
+                std::process::exit(EXIT_CONFIG_ERROR);
+            }
+        });
+
+    let min_alpha_ratio = cli
+        .min_alpha_ratio
+        .unwrap_or(tokenizer::DEFAULT_MIN_ALPHA_RATIO);
+    if !(0.0..=1.0).contains(&min_alpha_ratio) {
+        eprintln!("Error: --min-alpha-ratio must be between 0 and 1");
+        std::process::exit(EXIT_CONFIG_ERROR);
+    }
+
+    let login_fields: Vec<(String, String)> = cli
+        .login_field
+        .iter()
+        .map(|field| match field.split_once('=') {
+            Some((name, value)) => (name.trim().to_string(), value.to_string()),
+            None => {
+                eprintln!("Error: Invalid login field format: {}", field);
+                std::process::exit(EXIT_CONFIG_ERROR);
+            }
+        })
+        .collect();
+
+    if let Some(rate) = cli.rate {
+        if !(rate.is_finite() && rate > 0.0) {
+            eprintln!("Error: --rate must be a positive number of requests per second");
+            std::process::exit(EXIT_CONFIG_ERROR);
+        }
+    }
+
+    let (preset_delay, preset_jitter, preset_rotate) = cli
+        .politeness
+        .map(|politeness| politeness.settings())
+        .unwrap_or((0, 0, false));
+
+    let config = CrawlConfig {
+        max_depth: cli.depth.unwrap_or(2) as u32,
+        common_words_limit: cli.common.unwrap_or(400).min(1000) as usize,
+        follow_offsite: cli.offsite,
+        min_length: cli.min.unwrap_or(4) as usize,
+        user_agent: cli.agent,
+        headers,
+        parse_status: cli.parse_status,
+        preflight: cli.preflight,
+        max_body_size: cli.max_body_size,
+        cache,
+        by_page: cli.by_page,
+        crypto: cli.crypto.then(CryptoMatcher::new),
+        respect_meta_robots: cli.respect_meta_robots,
+        min_tls: cli.min_tls.map(|min_tls| min_tls.version()),
+        find_emails: cli.email || cli.profile || cli.jsonld,
+        find_socials: cli.social || cli.profile || cli.jsonld,
+        find_phones: cli.phone || cli.profile || cli.jsonld,
+        contacts: ContactMatcher::new(cli.deobfuscate_emails),
+        tech,
+        sensitive,
+        find_forms: cli.forms,
+        follow_forms: cli.follow_forms,
+        find_params: cli.params,
+        report_cookies: cli.report_cookies,
+        report_headers: cli.report_headers,
+        backoff_on_block: cli.backoff_on_block,
+        graph: cli.graph.is_some(),
+        graph_offsite: cli.graph_offsite,
+        tokenizer: Tokenizer::new(
+            cli.ascii_only,
+            cli.normalize_form,
+            min_alpha_ratio,
+            match cli.split_contractions {
+                Some(contractions) => Apostrophes::Contractions(contractions),
+                None if cli.strip_apostrophes => Apostrophes::Strip,
+                None => Apostrophes::Keep,
+            },
+            word_regex,
+        ),
+        strip_params: match cli.strip_params {
+            Some(names) if names.is_empty() => DEFAULT_STRIP_PARAMS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            Some(names) => names.iter().map(|name| name.to_ascii_lowercase()).collect(),
+            None => Vec::new(),
+        },
+        jsonld: cli.jsonld,
+        jsonld_raw: cli.jsonld_raw,
+        delay: Duration::from_millis(cli.delay.unwrap_or(preset_delay)),
+        jitter: Duration::from_millis(cli.jitter.unwrap_or(preset_jitter)),
+        rotate_agent: cli.rotate_agent || preset_rotate,
+        decode_entities: !cli.no_entity_decode,
+        percent_decode: cli.percent_decode,
+        cookies: cli.login_url.is_some().then(|| Arc::new(Jar::default())),
+        fetcher,
+        prominence: cli.rank == Rank::Prominence,
+        offsite_depth: cli.offsite_depth.map(u32::from),
+        seed_domain: seed_url.domain().map(str::to_string),
+        count_words: !cli.nowords,
+        document_frequency: cli.document_frequency,
+        meta_weight: cli.meta_words.then_some(cli.meta_weight),
+        http1_only: cli.http1_only,
+        exclude_hidden: cli.exclude_hidden,
+        data_attrs: cli
+            .data_attrs
+            .iter()
+            .map(|attr| attr.trim().to_ascii_lowercase())
+            .collect(),
+        dump,
+        track_word_pages: cli.min_pages.is_some(),
+        word_sources: cli.word_sources,
+        proxy,
+        header_templates,
+        seed_host: seed_url.host_str().unwrap_or_default().to_string(),
+        canonical_dedup: cli.canonical_dedup,
+        keep_pages: cli.by_page || cli.sqlite.is_some(),
+        retry: RetryPolicy {
+            respect_retry_after: cli.respect_retry_after,
+            non_idempotent: cli.retry_non_idempotent,
+        },
+        max_pagination: cli.max_pagination,
+        fuzzy_host: cli.fuzzy_host,
+        treat_hashbang_as_page: cli.treat_hashbang_as_page,
+        subdomains,
+        find_images: cli.images || cli.image_exif,
+        parse_pdf: cli.parse_pdf,
+        skip_similar: cli.skip_similar,
+        offsite_once: cli.offsite_once,
+        accept_hostname_mismatch: cli.accept_hostname_mismatch,
+        feeds: cli.feeds,
+        strict_scope: cli.strict_scope,
+        prefer_https: cli.prefer_https,
+        by_depth: cli.by_depth,
+        min_content_length: cli.min_content_length,
+        max_links_per_page: cli.max_links_per_page,
+        ca_certs,
+        resolver,
+    };
+
+    let mut state = CrawlState {
+        visited_urls: HashSet::new(),
+        links: Vec::new(),
+        pages: Vec::new(),
+        crypto: HashMap::new(),
+        parsed_pages: 0,
+        emails: HashMap::new(),
+        socials: HashMap::new(),
+        technologies: HashMap::new(),
+        sensitive: HashMap::new(),
+        forms: HashMap::new(),
+        params: HashSet::new(),
+        cookies: HashSet::new(),
+        header_report: HashMap::new(),
+        throttles: HashMap::new(),
+        edges: HashSet::new(),
+        names: HashMap::new(),
+        phones: HashMap::new(),
+        jsonld_blocks: Vec::new(),
+        scores: HashMap::new(),
+        hosts: HashMap::new(),
+        errors: Vec::new(),
+        word_pages: HashMap::new(),
+        word_sources: HashMap::new(),
+        canonicals: HashSet::new(),
+        pagination: HashMap::new(),
+        subdomains: HashSet::new(),
+        rate_limit: cli.rate.map(TokenBucket::new),
+        images: HashMap::new(),
+        seen_pages: simhash::SeenPages::default(),
+        similar_to: HashMap::new(),
+        approx: cli.approx_counts.map(ApproxCounter::new),
+        hsts_hosts: HashMap::new(),
+        plain_http_hosts: HashSet::new(),
+        depths: Vec::new(),
+        shuffle: cli.shuffle_links.then(|| match cli.shuffle_seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }),
+        stdin_page: None,
+        timings: cli.benchmark.then(Timings::default),
+        parse_started: None,
+        #[cfg(feature = "tui")]
+        dashboard: cli.tui.then(|| {
+            tui::Dashboard::start().unwrap_or_else(|err| {
+                eprintln!("Error: Unable to start the dashboard: {}", err);
+                std::process::exit(EXIT_CONFIG_ERROR);
+            })
+        }),
+    };
+
+    if let Some(ref login_url) = cli.login_url {
+        let login_url = Url::parse(login_url).unwrap_or_else(|err| {
+            eprintln!("Error: Invalid URL '{}': {}", login_url, err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        });
+        if let Err(err) = http_client(&login_url, &config)
+            .and_then(|client| login::submit(&client, &login_url, &login_fields, config.retry))
+        {
+            eprintln!("Error: Login at '{}' failed: {}", login_url, err);
+            std::process::exit(EXIT_SEED_FAILED);
+        }
+    }
+
+    let mut email_domains: Vec<String> = cli
+        .email_domains
+        .iter()
+        .map(|domain| domain.trim().trim_start_matches('@').to_lowercase())
+        .collect();
+    if cli.only_target_emails {
+        match seed_url.host_str().and_then(psl::domain_str) {
+            Some(domain) => email_domains.push(domain.to_lowercase()),
+            None => {
+                eprintln!("Error: The seed URL has no registrable domain to keep emails from");
+                std::process::exit(EXIT_CONFIG_ERROR);
+            }
+        }
+    }
+
+    let mut mx_checker = cli.verify_mx.then(|| {
+        mx::MxChecker::new().unwrap_or_else(|err| {
+            eprintln!("Error: Unable to set up DNS resolver: {}", err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        })
+    });
+
+    if let Some(ref path) = cli.skip_visited {
+        match load_visited(path, &config) {
+            Ok(visited) => state.visited_urls = visited,
+            Err(err) => {
+                eprintln!("Error: Unable to read '{}': {}", path, err);
+                std::process::exit(EXIT_CONFIG_ERROR);
+            }
+        }
+        state
+            .visited_urls
+            .remove(&normalize_url(&seed_url, &config));
+    }
+
+    if cli.stdin {
+        let mut body = Vec::new();
+        if let Err(err) = io::stdin().read_to_end(&mut body) {
+            eprintln!("Error: Unable to read standard input: {}", err);
+            std::process::exit(EXIT_CONFIG_ERROR);
+        }
+        state.stdin_page = Some((normalize_url(&seed_url, &config), body));
+    }
+
+    let mut exit_code = EXIT_SUCCESS;
+
+    let crawl_started = Instant::now();
+    let (links_before, errors_before) = (state.links.len(), state.errors.len());
+    let mut result = unique_words_from_url(&seed_url, &mut state, &config);
+    let crawl_time = crawl_started.elapsed();
+    // The seed is the first link fetched, an error entry for it means the crawl got nowhere
+    let seed_error = state.links.get(links_before).and_then(|(seed, _)| {
+        state.errors[errors_before..]
+            .iter()
+            .find(|error| &error.url == seed && matches!(error.kind, "http" | "scope"))
+            .map(|error| error.message.clone())
+    });
+    // Gives the terminal back before anything else is printed
+    #[cfg(feature = "tui")]
+    {
+        state.dashboard = None;
+    }
+    if let Some(approx) = state.approx.take() {
+        result = result.map(|_| approx.into_counts());
+    }
+    if let Some(threshold) = cli.dedup_near {
+        result = result.map(|word_count| dedup::merge_near_duplicates(word_count, threshold));
+        for page in &mut state.pages {
+            page.words = dedup::merge_near_duplicates(std::mem::take(&mut page.words), threshold);
+        }
+    }
+
+    if !email_domains.is_empty() {
+        state
+            .emails
+            .retain(|email, _| email_in_domains(email, &email_domains));
+    }
+
+    // Deliverability per email: Some(false) for domains that refuse mail, None when unknown
+    let mut mx: Option<HashMap<String, Option<bool>>> = None;
+    if let Some(ref mut checker) = mx_checker {
+        mx = Some(
+            state
+                .emails
+                .keys()
+                .map(|email| {
+                    let domain = email.rsplit_once('@').map_or("", |(_, domain)| domain);
+                    (email.clone(), checker.deliverable(domain))
+                })
+                .collect(),
+        );
+    }
+
+    if let Some(min_pages) = cli.min_pages {
+        if let Ok(ref mut word_count) = result {
+            word_count
+                .retain(|word, _| state.word_pages.get(word).copied().unwrap_or(0) >= min_pages);
+        }
+    }
+
+    // Images that could not be fetched or carry no EXIF block are left out
+    let mut image_exif: Option<HashMap<String, ExifFields>> = None;
+    if cli.image_exif {
+        let mut found = HashMap::new();
+        let image_urls: Vec<String> = sorted_keys(&state.images).into_iter().cloned().collect();
+        for image in image_urls {
+            if let Ok(url) = Url::parse(&image) {
+                if let Ok(Some(fields)) = fetch_image_exif(&url, &mut state, &config) {
+                    found.insert(image, fields);
+                }
+            }
+        }
+        image_exif = Some(found);
+    }
+
+    if cli.rank == Rank::Tfidf {
+        if let Ok(ref word_count) = result {
+            state.scores = tfidf::scores(word_count, &tfidf::background_ranks());
+        }
+    }
+
+    if let Some(ref path) = cli.report {
+        let no_words = HashMap::new();
+        let page = html_report(
+            &seed,
+            result.as_ref().unwrap_or(&no_words),
+            &state,
+            min_count,
+            cli.sort,
+            (cli.rank != Rank::Count).then_some(&state.scores),
+            cli.top,
+        );
+        write_document(Some(path), &page, "Report contents", cli.gzip_output);
+    }
+
+    match result {
+        Ok(word_count) if cli.profile => {
+            let words = top_words(
+                ranked_words(
+                    &word_count,
+                    min_count,
+                    cli.sort,
+                    (cli.rank != Rank::Count).then_some(&state.scores),
+                ),
+                Some(cli.top.unwrap_or(PROFILE_WORDS)),
+            );
+            for (&format, &path) in cli.format.iter().zip(&word_files) {
+                let report = profile_report(&seed, &word_count, &state, &words, format);
+                match format {
+                    OutputFormat::Text => write_lines(path, &report, "Profile", cli.gzip_output),
+                    OutputFormat::Json => {
+                        write_document(path, &report.join("\n"), "Profile", cli.gzip_output)
+                    }
+                }
+            }
+        }
+        // Without a wordlist, text output has nothing to write to the wordlist file
+        Ok(_) if cli.nowords && cli.format == [OutputFormat::Text] => {}
+        Ok(word_count) => {
+            let sorted_word_count = top_words(
+                ranked_words(
+                    &word_count,
+                    min_count,
+                    cli.sort,
+                    (cli.rank != Rank::Count).then_some(&state.scores),
+                ),
+                cli.top,
+            );
+
+            for (&format, &path) in cli.format.iter().zip(&word_files) {
+                if cli.nowords && format == OutputFormat::Text {
+                    continue;
+                }
+                let output_file_path = path.unwrap_or("output.txt");
+                let mut file = create_output(Some(output_file_path), cli.gzip_output);
+                match format {
+                    OutputFormat::Text => {
+                        for (word, count) in &sorted_word_count {
+                            writeln!(file, "{}: {}", escape_controls(word), count)
+                                .expect("Unable to write data");
+                        }
+                        // Per-page counts skip the minimum count, few words repeat much on a single page
+                        if config.by_page {
+                            for page in &state.pages {
+                                writeln!(file, "\n# {}", page.url).expect("Unable to write data");
+                                for (word, count) in
+                                    top_words(sorted_words(&page.words, 1, cli.sort), cli.top)
+                                {
+                                    writeln!(file, "{}: {}", escape_controls(word), count)
+                                        .expect("Unable to write data");
+                                }
+                            }
+                        }
+                        if config.by_depth {
+                            let new_words = new_words_by_depth(&state.depths);
+                            for (depth, words) in state.depths.iter().enumerate() {
+                                writeln!(
+                                    file,
+                                    "\n# depth {}: {} pages, {} unique words, {} new",
+                                    depth,
+                                    words.pages,
+                                    words.words.len(),
+                                    new_words[depth]
+                                )
+                                .expect("Unable to write data");
+                                for (word, count) in
+                                    top_words(sorted_words(&words.words, 1, cli.sort), cli.top)
+                                {
+                                    writeln!(file, "{}: {}", escape_controls(word), count)
+                                        .expect("Unable to write data");
+                                }
+                            }
+                        }
+                    }
+                    OutputFormat::Json => {
+                        let mut output = json!({});
+                        if config.count_words {
+                            output["words"] = words_json(
+                                &sorted_word_count,
+                                (cli.rank != Rank::Count).then_some(&state.scores),
+                                config.word_sources.map(|_| &state.word_sources),
+                            );
+                        }
+                        output["errors"] = errors_json(&state.errors);
+                        if config.follow_offsite || config.offsite_once {
+                            output["hosts"] = host_breakdown_json(&host_breakdown(&state));
+                        }
+                        if config.by_page && config.count_words {
+                            output["pages"] = state
+                            .pages
+                            .iter()
+                            .map(|page| {
+                                json!({
+                                    "url": page.url.as_str(),
+                                    "words": words_json(&top_words(sorted_words(&page.words, 1, cli.sort), cli.top), None, None),
+                                })
+                            })
+                            .collect();
+                        }
+                        if config.by_depth && config.count_words {
+                            let new_words = new_words_by_depth(&state.depths);
+                            output["depths"] = state
+                            .depths
+                            .iter()
+                            .enumerate()
+                            .map(|(depth, words)| {
+                                json!({
+                                    "depth": depth,
+                                    "pages": words.pages,
+                                    "unique_words": words.words.len(),
+                                    "new_words": new_words[depth],
+                                    "words": words_json(&top_words(sorted_words(&words.words, 1, cli.sort), cli.top), None, None),
+                                })
+                            })
+                            .collect();
+                        }
+                        if config.jsonld {
+                            output["names"] = sources_json("name", &state.names);
+                        }
+                        if config.find_phones {
+                            output["phones"] = sources_json("phone", &state.phones);
+                        }
+                        if config.jsonld_raw {
+                            output["jsonld"] = state
+                                .jsonld_blocks
+                                .iter()
+                                .map(|(url, block)| json!({ "url": url.as_str(), "data": block }))
+                                .collect();
+                        }
+                        if config.crypto.is_some() {
+                            let mut crypto = serde_json::Map::new();
+                            for (currency, addresses) in sorted_crypto(&state.crypto) {
+                                crypto.insert(currency.name().to_string(), json!(addresses));
+                            }
+                            output["crypto"] = crypto.into();
+                        }
+                        if config.find_forms {
+                            output["forms"] = sorted_forms(&state.forms)
+                            .iter()
+                            .map(|(action, method, fields)| {
+                                json!({ "action": action.as_str(), "method": method, "fields": fields })
+                            })
+                            .collect();
+                        }
+                        if config.find_params {
+                            output["params"] = json!(sorted_set(&state.params));
+                        }
+                        if config.report_cookies {
+                            output["cookies"] = sorted_cookies(&state.cookies)
+                                .iter()
+                                .map(|(host, cookie)| {
+                                    json!({
+                                        "host": host,
+                                        "name": cookie.name,
+                                        "domain": cookie.domain,
+                                        "path": cookie.path,
+                                        "secure": cookie.secure,
+                                        "http_only": cookie.http_only,
+                                        "same_site": cookie.same_site,
+                                        "persistent": cookie.persistent,
+                                        "issues": cookie.issues(),
+                                    })
+                                })
+                                .collect();
+                        }
+                        if config.report_headers {
+                            output["headers"] = sorted_header_report(&state.header_report)
+                                .iter()
+                                .map(|(host, headers)| {
+                                    json!({
+                                        "host": host,
+                                        "pages": headers.pages,
+                                        "headers": headers.values,
+                                        "missing": headers.missing,
+                                        "issues": headers.issues(),
+                                    })
+                                })
+                                .collect();
+                        }
+                        if config.sensitive.is_some() {
+                            output["sensitive"] = sorted_sensitive(&state.sensitive)
+                            .iter()
+                            .map(|(category, url)| json!({ "category": category, "url": url.as_str() }))
+                            .collect();
+                        }
+                        if config.tech.is_some() {
+                            output["technologies"] =
+                                technologies_json(&sorted_technologies(&state.technologies));
+                        }
+                        if config.find_emails {
+                            output["emails"] = sources_json("email", &state.emails);
+                            if let Some(ref mx) = mx {
+                                for entry in output["emails"].as_array_mut().into_iter().flatten() {
+                                    let deliverable =
+                                        entry["email"].as_str().and_then(|email| mx[email]);
+                                    entry["mx"] = json!(deliverable);
+                                }
+                            }
+                        }
+                        if config.find_socials {
+                            output["socials"] = sources_json("account", &state.socials);
+                        }
+                        if config.subdomains.is_some() {
+                            output["subdomains"] = json!(sorted_set(&state.subdomains));
+                        }
+                        if config.find_images {
+                            output["images"] = images_json(&state.images, image_exif.as_ref());
+                        }
+                        serde_json::to_writer_pretty(&mut file, &output)
+                            .expect("Unable to write data");
+                        writeln!(file).expect("Unable to write data");
+                    }
+                }
+                drop(file);
+                report_written("Results", output_file_path, cli.gzip_output);
+            }
+
+            if let Some(ref rules) = mangle_rules {
+                write_lines(
+                    cli.manglefile.as_deref(),
+                    &mangle::candidates(rules, sorted_word_count.iter().map(|(word, _)| *word)),
+                    "Password candidates",
+                    cli.gzip_output,
+                );
+            }
+
+            if let Some(ref path) = cli.wordcloud {
+                let weights: Vec<(&String, f64)> = sorted_word_count
+                    .iter()
+                    .map(|&(word, &count)| match cli.rank {
+                        Rank::Count => (word, count as f64),
+                        _ => (word, state.scores.get(word).copied().unwrap_or(0.0)),
+                    })
+                    .collect();
+                let lines =
+                    wordcloud::lines(path, &weights, cli.top.unwrap_or(wordcloud::DEFAULT_WORDS));
+                if wordcloud::is_json(path) {
+                    write_document(
+                        Some(path),
+                        &lines.join("\n"),
+                        "Word cloud sizes",
+                        cli.gzip_output,
+                    );
+                } else {
+                    write_lines(Some(path), &lines, "Word cloud sizes", cli.gzip_output);
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            exit_code = EXIT_SEED_FAILED;
+        }
+    }
+    if let Some(message) = seed_error {
+        eprintln!("Error: The seed URL failed: {}", message);
+        exit_code = EXIT_SEED_FAILED;
+    }
+    warn_unresolved_internal(
+        &state
+            .errors
+            .iter()
+            .filter(|error| error.message.contains("dns error"))
+            .filter_map(|error| internal_host(&error.url))
+            .collect(),
+    );
+
+    if cli.format.contains(&OutputFormat::Text) && !cli.profile {
+        if config.find_emails {
+            let lines: Vec<String> = sorted_keys(&state.emails)
+                .into_iter()
+                .map(|email| match mx.as_ref().map(|mx| mx[email]) {
+                    Some(Some(false)) => format!("{} (no MX)", email),
+                    Some(None) => format!("{} (MX unknown)", email),
+                    _ => email.clone(),
+                })
+                .collect();
+            write_lines(cli.emfile.as_deref(), &lines, "Emails", cli.gzip_output);
+        }
+        if config.find_socials {
+            let lines: Vec<String> = sorted_keys(&state.socials).into_iter().cloned().collect();
+            write_lines(cli.socfile.as_deref(), &lines, "Socials", cli.gzip_output);
+        }
+        if cli.phone {
+            let lines: Vec<String> = sorted_keys(&state.phones).into_iter().cloned().collect();
+            write_lines(
+                cli.phonefile.as_deref(),
+                &lines,
+                "Phone numbers",
+                cli.gzip_output,
+            );
+        }
+        if config.find_images {
+            let lines: Vec<String> = sorted_keys(&state.images)
+                .into_iter()
+                .map(
+                    |image| match image_exif.as_ref().and_then(|exif| exif.get(image)) {
+                        Some(fields) if !fields.is_empty() => {
+                            let fields: Vec<String> = fields
+                                .iter()
+                                .map(|(name, value)| format!("{}={}", name, value))
+                                .collect();
+                            format!("{} {}", image, fields.join("; "))
+                        }
+                        _ => image.clone(),
+                    },
+                )
+                .collect();
+            write_lines(cli.imagefile.as_deref(), &lines, "Images", cli.gzip_output);
+        }
+        if config.subdomains.is_some() {
+            let lines: Vec<String> = sorted_set(&state.subdomains).into_iter().cloned().collect();
+            write_lines(
+                cli.subdomainfile.as_deref(),
+                &lines,
+                "Subdomains",
+                cli.gzip_output,
+            );
+        }
+        if config.jsonld {
+            let mut lines: Vec<String> = sorted_keys(&state.names)
+                .iter()
+                .map(|name| format!("name: {}", name))
+                .collect();
+            lines.extend(
+                sorted_keys(&state.phones)
+                    .iter()
+                    .map(|phone| format!("phone: {}", phone)),
+            );
+            lines.extend(
+                state
+                    .jsonld_blocks
+                    .iter()
+                    .map(|(url, block)| format!("{} {}", url, block)),
+            );
+            write_lines(
+                cli.jsonldfile.as_deref(),
+                &lines,
+                "JSON-LD details",
+                cli.gzip_output,
+            );
+        }
+        if config.crypto.is_some() {
+            let mut lines = Vec::new();
+            for (currency, addresses) in sorted_crypto(&state.crypto) {
+                for address in addresses {
+                    lines.push(format!("{}: {}", currency.name(), address));
+                }
+            }
+            write_lines(
+                cli.cryptofile.as_deref(),
+                &lines,
+                "Crypto addresses",
+                cli.gzip_output,
+            );
+        }
+        if config.find_forms {
+            let lines: Vec<String> = sorted_forms(&state.forms)
+                .iter()
+                .map(|(action, method, fields)| {
+                    let fields: Vec<&str> = fields.iter().map(|field| field.as_str()).collect();
+                    format!("{} {} {}", method, action, fields.join(","))
+                })
+                .collect();
+            write_lines(cli.formfile.as_deref(), &lines, "Forms", cli.gzip_output);
+        }
+        if config.find_params {
+            let lines: Vec<String> = sorted_set(&state.params).into_iter().cloned().collect();
+            write_lines(
+                cli.paramfile.as_deref(),
+                &lines,
+                "Parameters",
+                cli.gzip_output,
+            );
+        }
+        if config.report_cookies {
+            let lines: Vec<String> = sorted_cookies(&state.cookies)
+                .into_iter()
+                .map(|(host, cookie)| cookie_line(host, cookie))
+                .collect();
+            write_lines(
+                cli.cookiefile.as_deref(),
+                &lines,
+                "Cookies",
+                cli.gzip_output,
+            );
+        }
+        if config.report_headers {
+            let mut lines = Vec::new();
+            for (host, headers) in sorted_header_report(&state.header_report) {
+                lines.push(format!("# {}, {} pages", host, headers.pages));
+                for (name, values) in &headers.values {
+                    for value in values {
+                        lines.push(format!("{}: {}", name, value));
+                    }
+                }
+                for issue in headers.issues() {
+                    lines.push(format!("! {}", issue));
+                }
+            }
+            write_lines(
+                cli.headerfile.as_deref(),
+                &lines,
+                "Header summaries",
+                cli.gzip_output,
+            );
+        }
+        if config.sensitive.is_some() {
+            // A heading line per category, then its links
+            let mut lines = Vec::new();
+            let mut current = None;
+            for (category, url) in sorted_sensitive(&state.sensitive) {
+                if current != Some(category) {
+                    lines.push(format!("# {}", category));
+                    current = Some(category);
+                }
+                lines.push(url.to_string());
+            }
+            write_lines(
+                cli.sensitivefile.as_deref(),
+                &lines,
+                "Sensitive paths",
+                cli.gzip_output,
+            );
+        }
+        if config.tech.is_some() {
+            let lines: Vec<String> = sorted_technologies(&state.technologies)
+                .iter()
+                .map(|(name, evidence)| format!("{} ({})", name, evidence))
+                .collect();
+            write_lines(
+                cli.techfile.as_deref(),
+                &lines,
+                "Technologies",
+                cli.gzip_output,
+            );
+        }
+    }
+
+    if let Some(ref graph_file_path) = cli.graph {
+        write_lines(
+            Some(graph_file_path),
+            &graph_dot(&state.edges),
+            "Link graph",
+            cli.gzip_output,
+        );
+    }
+
+    if let Some(link_file_path) = cli.linkfile {
+        let mut file = create_output(Some(&link_file_path), cli.gzip_output);
+        for (link, status) in &state.links {
+            match state.similar_to.get(link) {
+                Some(similar) => writeln!(file, "{} {} (similar to {})", status, link, similar),
+                None => writeln!(file, "{} {}", status, link),
+            }
+            .expect("Unable to write data");
+        }
+        report_written("Links", &link_file_path, cli.gzip_output);
+    }
+
+    if let Some(ref log_path) = cli.politeness_log {
+        write_lines(
+            Some(log_path),
+            &politeness_log(&state.throttles, &config, cli.rate),
+            "Politeness audit records",
+            cli.gzip_output,
+        );
+    }
+
+    if let Some(ref sqlite_path) = cli.sqlite {
+        let pages: Vec<(&Url, &HashMap<String, u32>)> = state
+            .pages
+            .iter()
+            .map(|page| (&page.url, &page.words))
+            .collect();
+        match sqlite::write(
+            sqlite_path,
+            &pages,
+            &state.emails,
+            &state.socials,
+            &state.links,
+        ) {
+            Ok(()) => report_written("Database records", sqlite_path, cli.gzip_output),
+            Err(err) => {
+                eprintln!("Error: Unable to write database '{}': {}", sqlite_path, err);
+                exit_code = EXIT_CONFIG_ERROR;
+            }
+        }
+    }
+
+    if let Some(error_file_path) = cli.errorfile {
+        let mut file = create_output(Some(&error_file_path), cli.gzip_output);
+        for error in &state.errors {
+            writeln!(
+                file,
+                "{} {} {} {}",
+                error.kind,
+                error.depth,
+                error.url,
+                escape_controls(&error.message)
+            )
+            .expect("Unable to write data");
+        }
+        report_written("Errors", &error_file_path, cli.gzip_output);
+    }
+
+    if let Some(min_pages) = cli.warn_thin {
+        if state.parsed_pages < min_pages {
+            eprintln!(
+                "Warning: only {} page(s) were parsed, fewer than the {} expected. \
+                 Check the crawl scope, robots directives, and whether the target is blocking requests.",
+                state.parsed_pages, min_pages
+            );
+        }
+    }
+
+    if let Some(ref timings) = state.timings {
+        for line in timings.summary(crawl_time, state.parsed_pages) {
+            eprintln!("{}", line);
+        }
+    }
+
+    if let Some(ref dir) = cli.output_dir {
+        write_manifest(Path::new(dir), &seed, &outputs, cli.gzip_output);
+    }
+
+    if exit_code == EXIT_SUCCESS && !state.errors.is_empty() {
+        exit_code = EXIT_PAGE_ERRORS;
+    }
+    std::process::exit(exit_code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capitalised_common_words_filter_lowercase_words() {
+        let common = common_words("The\n AND \nof\nwhere\n".as_bytes(), 3);
+        assert!(common.contains("the"));
+        assert!(common.contains("and"));
+        assert!(common.contains("of"));
+        // Only the first entries up to the limit are used
+        assert!(!common.contains("where"));
+    }
+
+    #[test]
+    fn terminal_escape_sequences_are_escaped() {
+        assert_eq!(
+            escape_controls("\x1b[31mred\x1b[0m"),
+            "\\u{1b}[31mred\\u{1b}[0m"
+        );
+        assert_eq!(escape_controls("bell\x07"), "bell\\u{7}");
+        assert_eq!(escape_controls("abc\u{202e}fdp.exe"), "abc\\u{202e}fdp.exe");
+        assert!(matches!(escape_controls("plain café"), Cow::Borrowed(_)));
     }
 }
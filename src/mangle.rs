@@ -0,0 +1,185 @@
+/*
+ * Password candidate generation for --mangle.
+ *
+ * Every word of the wordlist is run through every rule, and the distinct results are written in
+ * wordlist order, so candidates from the most frequent words come first. Rules are written in the
+ * hashcat rule syntax, of which this subset is understood:
+ *
+ *     :    leave the word as it is       r    reverse it
+ *     l    lowercase it                  d    repeat it, word becomes wordword
+ *     u    uppercase it                  [    delete the first character
+ *     c    capitalise it                 ]    delete the last character
+ *     C    lowercase the first character and uppercase the rest
+ *     t    toggle the case of every character
+ *     $X   append character X            ^X   prepend character X
+ *     sXY  replace every X with Y
+ *
+ * A rule applies its functions left to right, and spaces between them are ignored. A rules file
+ * has one rule per line; blank lines and lines starting with '#' are ignored. Without a file the
+ * BUILTIN_RULES are used: the word itself, capitalised and uppercased, with 1, 123 or ! appended,
+ * and in leetspeak.
+ */
+
+use std::{collections::HashSet, error::Error, fs};
+
+const BUILTIN_RULES: &[&str] = &[
+    ":",
+    "c",
+    "u",
+    "$1",
+    "c $1",
+    "$1 $2 $3",
+    "c $1 $2 $3",
+    "$!",
+    "c $!",
+    "sa4 se3 si1 so0",
+    "c sa4 se3 si1 so0",
+];
+
+enum Function {
+    Nothing,
+    Lower,
+    Upper,
+    Capitalize,
+    InvertCapitalize,
+    Toggle,
+    Reverse,
+    Duplicate,
+    DeleteFirst,
+    DeleteLast,
+    Append(char),
+    Prepend(char),
+    Replace(char, char),
+}
+
+pub struct Rule(Vec<Function>);
+
+pub fn parse_rule(rule: &str) -> Result<Rule, String> {
+    let mut functions = Vec::new();
+    let mut chars = rule.chars();
+    while let Some(name) = chars.next() {
+        // Spaces separate functions, but are characters like any other as arguments
+        if name == ' ' {
+            continue;
+        }
+        let mut argument = || {
+            chars
+                .next()
+                .ok_or_else(|| format!("'{}' is missing its character", name))
+        };
+        let function = match name {
+            ':' => Function::Nothing,
+            'l' => Function::Lower,
+            'u' => Function::Upper,
+            'c' => Function::Capitalize,
+            'C' => Function::InvertCapitalize,
+            't' => Function::Toggle,
+            'r' => Function::Reverse,
+            'd' => Function::Duplicate,
+            '[' => Function::DeleteFirst,
+            ']' => Function::DeleteLast,
+            '$' => Function::Append(argument()?),
+            '^' => Function::Prepend(argument()?),
+            's' => Function::Replace(argument()?, argument()?),
+            other => return Err(format!("'{}' is not a supported rule function", other)),
+        };
+        functions.push(function);
+    }
+    Ok(Rule(functions))
+}
+
+pub fn builtin_rules() -> Vec<Rule> {
+    BUILTIN_RULES
+        .iter()
+        .filter_map(|rule| parse_rule(rule).ok())
+        .collect()
+}
+
+pub fn load_rules(path: &str) -> Result<Vec<Rule>, Box<dyn Error>> {
+    let mut rules = Vec::new();
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let rule = parse_rule(line)
+            .map_err(|err| format!("Invalid rule on line {}: {}", number + 1, err))?;
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+impl Rule {
+    fn apply(&self, word: &str) -> String {
+        let mut word = word.to_string();
+        for function in &self.0 {
+            word = match *function {
+                Function::Nothing => word,
+                Function::Lower => word.to_lowercase(),
+                Function::Upper => word.to_uppercase(),
+                Function::Capitalize => capitalize(&word),
+                Function::InvertCapitalize => {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first
+                            .to_lowercase()
+                            .chain(chars.flat_map(char::to_uppercase))
+                            .collect(),
+                        None => word,
+                    }
+                }
+                Function::Toggle => word
+                    .chars()
+                    .flat_map(|c| {
+                        if c.is_uppercase() {
+                            c.to_lowercase().collect::<Vec<char>>()
+                        } else {
+                            c.to_uppercase().collect()
+                        }
+                    })
+                    .collect(),
+                Function::Reverse => word.chars().rev().collect(),
+                Function::Duplicate => word.repeat(2),
+                Function::DeleteFirst => word.chars().skip(1).collect(),
+                Function::DeleteLast => {
+                    let mut word = word;
+                    word.pop();
+                    word
+                }
+                Function::Append(c) => {
+                    let mut word = word;
+                    word.push(c);
+                    word
+                }
+                Function::Prepend(c) => format!("{}{}", c, word),
+                Function::Replace(from, to) => word.replace(from, &to.to_string()),
+            };
+        }
+        word
+    }
+}
+
+pub fn candidates<'a>(rules: &[Rule], words: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut candidates = Vec::new();
+    for word in words {
+        for rule in rules {
+            let candidate = rule.apply(word);
+            if !candidate.is_empty() && seen.insert(candidate.clone()) {
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
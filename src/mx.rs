@@ -0,0 +1,53 @@
+/*
+ * Deliverability checks for the domains of harvested email addresses, used by --verify-mx.
+ *
+ * A domain is deliverable when it publishes MX records or, failing that, an address record,
+ * which mail servers fall back to. A "null MX" (a single record pointing at ".") explicitly
+ * refuses mail. Lookups that fail for any other reason, such as timeouts, leave the domain
+ * unknown. So do .onion domains, which only resolve inside Tor and are not looked up. Results
+ * are cached per domain, so each one is only looked up once.
+ */
+
+use std::{collections::HashMap, io};
+
+use hickory_resolver::{error::ResolveErrorKind, Resolver};
+
+pub struct MxChecker {
+    resolver: Resolver,
+    cache: HashMap<String, Option<bool>>,
+}
+
+impl MxChecker {
+    pub fn new() -> io::Result<MxChecker> {
+        Ok(MxChecker {
+            resolver: Resolver::from_system_conf()?,
+            cache: HashMap::new(),
+        })
+    }
+
+    pub fn deliverable(&mut self, domain: &str) -> Option<bool> {
+        if let Some(&known) = self.cache.get(domain) {
+            return known;
+        }
+        if domain.ends_with(".onion") {
+            return None;
+        }
+        // The trailing dot keeps the system search domains from being appended
+        let name = format!("{}.", domain.trim_end_matches('.'));
+        let deliverable = match self.resolver.mx_lookup(name.as_str()) {
+            Ok(records) => Some(records.iter().any(|record| !record.exchange().is_root())),
+            Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                match self.resolver.lookup_ip(name.as_str()) {
+                    Ok(_) => Some(true),
+                    Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                        Some(false)
+                    }
+                    Err(_) => None,
+                }
+            }
+            Err(_) => None,
+        };
+        self.cache.insert(domain.to_string(), deliverable);
+        deliverable
+    }
+}
@@ -0,0 +1,49 @@
+/*
+ * Text extraction from PDF documents, used by --parse-pdf.
+ *
+ * A PDF is recognised by its Content-Type or, for servers that send it as something generic, by
+ * the %PDF- signature its file starts with. Its text is extracted with pdf-extract and handed to
+ * the rest of the crawl as a page holding that text in a single <pre> element, so words, emails
+ * and everything else are found in it like in any other page and credited to the PDF's URL. Links
+ * inside PDFs are not followed. Encrypted PDFs, PDFs of scanned images without a text layer and
+ * PDFs the extractor cannot read are skipped.
+ */
+
+use std::panic;
+
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+
+pub fn is_pdf_content(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| {
+            content_type
+                .to_ascii_lowercase()
+                .starts_with("application/pdf")
+        })
+}
+
+pub fn is_pdf(headers: &HeaderMap, body: &[u8]) -> bool {
+    is_pdf_content(headers) || body.starts_with(b"%PDF-")
+}
+
+pub fn extract_text(body: &[u8]) -> Result<String, String> {
+    // The extractor panics on some malformed files, which should only cost that one document
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(|| pdf_extract::extract_text_from_mem(body));
+    panic::set_hook(hook);
+    match result {
+        Ok(Ok(text)) => Ok(text),
+        Ok(Err(err)) => Err(err.to_string()),
+        Err(_) => Err("the document could not be read".to_string()),
+    }
+}
+
+pub fn as_html(text: &str) -> String {
+    format!(
+        "<html><body><pre>{}</pre></body></html>",
+        html_escape::encode_text(text)
+    )
+}
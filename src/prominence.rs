@@ -0,0 +1,28 @@
+/*
+ * Prominence scoring of words, used by --rank prominence.
+ *
+ * Every counted occurrence of a word scores one point plus a bonus for the element it appears in
+ * and a bonus for appearing early on its page:
+ *
+ *     occurrence = 1 + tag weight + max(0, 1 - position / EARLY_WORDS)
+ *
+ * The tag weight is 2 for h1, 1 for h2 and h3, 0.5 for h4 to h6 and 0 anywhere else, and the
+ * position is the number of words already counted on the page. On top of that, each word in a
+ * page's <title> adds TITLE_WEIGHT once for that page. A word's score is the sum over the crawl.
+ */
+
+pub const TITLE_WEIGHT: f64 = 3.0;
+const EARLY_WORDS: f64 = 200.0;
+
+fn tag_weight(tag: &str) -> f64 {
+    match tag {
+        "h1" => 2.0,
+        "h2" | "h3" => 1.0,
+        "h4" | "h5" | "h6" => 0.5,
+        _ => 0.0,
+    }
+}
+
+pub fn occurrence_score(tag: &str, position: usize) -> f64 {
+    1.0 + tag_weight(tag) + (1.0 - position as f64 / EARLY_WORDS).max(0.0)
+}
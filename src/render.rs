@@ -0,0 +1,113 @@
+/*
+ * JavaScript rendering of pages through a headless browser, for --render.
+ *
+ * The browser is a Chromium-based one driven over the DevTools protocol with chromiumoxide, and
+ * is started once for the whole crawl. Each page is loaded in a new tab under a virtual time
+ * budget, as Chromium's own --virtual-time-budget does: timers and animations run as fast as the
+ * page lets them, and time stands still while network requests are pending, so scripts get the
+ * same amount of work done however slow the site is. The DOM is read once the budget has run out,
+ * or after LOAD_TIMEOUT of real time past the budget for pages that never let it run out.
+ *
+ * Tabs do not share the crawl's cookies. The browser's requests go through the same proxy as the
+ * crawl when one is set, with host names resolved on the proxy side, which Chromium always does for
+ * SOCKS proxies. The browser's own resolver is switched off for everything but the proxy, so
+ * prefetching cannot leak names to local DNS either. Chromium cannot log in to a proxy, so proxies
+ * with a username and password are refused.
+ */
+
+use std::{error::Error, time::Duration};
+
+use chromiumoxide::{
+    cdp::browser_protocol::emulation::{
+        EventVirtualTimeBudgetExpired, SetVirtualTimePolicyParams, VirtualTimePolicy,
+    },
+    BrowserConfig,
+};
+use futures::StreamExt;
+use reqwest::Url;
+use tokio::runtime::Runtime;
+
+const LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct Browser {
+    runtime: Runtime,
+    browser: chromiumoxide::Browser,
+    budget_ms: u64,
+}
+
+impl Browser {
+    // Without a binary the usual install locations are searched
+    pub fn launch(
+        binary: Option<&str>,
+        budget_ms: u64,
+        proxy: Option<&str>,
+    ) -> Result<Browser, Box<dyn Error>> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+        let mut config = BrowserConfig::builder();
+        if let Some(binary) = binary {
+            config = config.chrome_executable(binary);
+        }
+        if let Some(proxy) = proxy {
+            let proxy_url = Url::parse(proxy)?;
+            if !proxy_url.username().is_empty() {
+                return Err(
+                    "the browser cannot log in to a proxy with a username and password".into(),
+                );
+            }
+            config = config
+                .arg(format!(
+                    "--proxy-server={}",
+                    proxy.replacen("socks5h://", "socks5://", 1)
+                ))
+                .arg(format!(
+                    "--host-resolver-rules=MAP * ~NOTFOUND , EXCLUDE {}",
+                    proxy_url.host_str().unwrap_or_default()
+                ));
+        }
+        let config = config.build()?;
+        let browser = runtime.block_on(async {
+            let (browser, mut handler) = chromiumoxide::Browser::launch(config).await?;
+            // The connection to the browser only makes progress while its handler is polled
+            tokio::spawn(async move { while handler.next().await.is_some() {} });
+            Ok::<_, Box<dyn Error>>(browser)
+        })?;
+        Ok(Browser {
+            runtime,
+            browser,
+            budget_ms,
+        })
+    }
+
+    pub fn render(&self, url: &Url, user_agent: Option<&str>) -> Result<String, Box<dyn Error>> {
+        self.runtime.block_on(async {
+            let page = self.browser.new_page("about:blank").await?;
+            if let Some(agent) = user_agent {
+                page.set_user_agent(agent).await?;
+            }
+            let mut expired = page
+                .event_listener::<EventVirtualTimeBudgetExpired>()
+                .await?;
+            page.execute(SetVirtualTimePolicyParams {
+                budget: Some(self.budget_ms as f64),
+                ..SetVirtualTimePolicyParams::new(VirtualTimePolicy::PauseIfNetworkFetchesPending)
+            })
+            .await?;
+            page.goto(url.as_str()).await?;
+            let budget = Duration::from_millis(self.budget_ms) + LOAD_TIMEOUT;
+            let _ = tokio::time::timeout(budget, expired.next()).await;
+            let html = page.content().await?;
+            page.close().await?;
+            Ok(html)
+        })
+    }
+}
+
+impl Drop for Browser {
+    fn drop(&mut self) {
+        let _ = self.runtime.block_on(self.browser.close());
+        let _ = self.runtime.block_on(self.browser.wait());
+    }
+}
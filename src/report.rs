@@ -0,0 +1,143 @@
+/*
+ * Standalone HTML report for --report.
+ *
+ * The page is built from the template in resources/report.html, compiled into the binary, by
+ * replacing its {{name}} placeholders. Styles are inlined and nothing is loaded from elsewhere,
+ * so the file can be mailed or attached to a finding as it is. Word counts are drawn as bars
+ * scaled to the top word. Every value taken from a crawled page is HTML escaped.
+ */
+
+use html_escape::encode_text;
+
+const TEMPLATE: &str = include_str!("resources/report.html");
+
+pub const DEFAULT_WORDS: usize = 50;
+
+pub struct Report<'a> {
+    pub seed: &'a str,
+    pub generated: String,
+    pub summary: Vec<(&'static str, String)>,
+    pub words: Vec<(&'a String, &'a u32)>,
+    // Titled lists such as emails and socials, left out when empty
+    pub lists: Vec<(&'static str, Vec<String>)>,
+    // Host, fetched, parsed, unique words and emails, only filled in when crawling offsite
+    pub hosts: Vec<[String; 5]>,
+}
+
+fn table(headings: &[&str], rows: impl Iterator<Item = String>) -> String {
+    let mut table = "<table>\n".to_string();
+    if !headings.is_empty() {
+        let headings: String = headings
+            .iter()
+            .map(|heading| format!("<th>{}</th>", heading))
+            .collect();
+        table.push_str(&format!("<tr>{}</tr>\n", headings));
+    }
+    table.extend(rows);
+    table.push_str("</table>");
+    table
+}
+
+fn words_table(words: &[(&String, &u32)]) -> String {
+    if words.is_empty() {
+        return "<p class=\"empty\">No words found</p>".to_string();
+    }
+    let top = words
+        .iter()
+        .map(|(_, &count)| count)
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    table(
+        &["Word", "Count", ""],
+        words.iter().map(|(word, &count)| {
+            format!(
+                "<tr><td>{}</td><td class=\"count\">{}</td><td class=\"bar\"><div style=\"width: {:.1}%\"></div></td></tr>\n",
+                encode_text(word),
+                count,
+                count as f64 / top as f64 * 100.0
+            )
+        }),
+    )
+}
+
+fn list(title: &str, items: &[String]) -> String {
+    let entries: String = items
+        .iter()
+        .map(|item| format!("<li>{}</li>\n", encode_text(item)))
+        .collect();
+    format!(
+        "<h2>{} ({})</h2>\n<ul>\n{}</ul>\n",
+        title,
+        items.len(),
+        entries
+    )
+}
+
+pub fn render(report: &Report) -> String {
+    let summary = table(
+        &[],
+        report.summary.iter().map(|(name, value)| {
+            format!(
+                "<tr><td>{}</td><td>{}</td></tr>\n",
+                name,
+                encode_text(value)
+            )
+        }),
+    );
+    let lists: String = report
+        .lists
+        .iter()
+        .filter(|(_, items)| !items.is_empty())
+        .map(|(title, items)| list(title, items))
+        .collect();
+    let hosts = if report.hosts.is_empty() {
+        String::new()
+    } else {
+        let rows = report.hosts.iter().map(|host| {
+            let cells: String = host
+                .iter()
+                .enumerate()
+                .map(|(column, cell)| match column {
+                    0 => format!("<td>{}</td>", encode_text(cell)),
+                    _ => format!("<td class=\"count\">{}</td>", cell),
+                })
+                .collect();
+            format!("<tr>{}</tr>\n", cells)
+        });
+        format!(
+            "<h2>Hosts</h2>\n{}",
+            table(&["Host", "Fetched", "Parsed", "Words", "Emails"], rows)
+        )
+    };
+    let seed = encode_text(report.seed);
+    let generated = encode_text(&report.generated);
+    let words = words_table(&report.words);
+    fill(
+        TEMPLATE,
+        &[
+            ("seed", &seed),
+            ("generated", &generated),
+            ("summary", &summary),
+            ("words", &words),
+            ("lists", &lists),
+            ("hosts", &hosts),
+        ],
+    )
+}
+
+// A single pass over the template, so placeholders inside crawled values are left alone
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut page = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some((before, after)) = rest.split_once("{{") {
+        let (name, after) = after.split_once("}}").unwrap_or((after, ""));
+        page.push_str(before);
+        if let Some((_, value)) = values.iter().find(|(key, _)| *key == name) {
+            page.push_str(value);
+        }
+        rest = after;
+    }
+    page.push_str(rest);
+    page
+}
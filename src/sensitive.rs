@@ -0,0 +1,104 @@
+/*
+ * Flagging of discovered links to interesting paths, used by --flag-sensitive.
+ *
+ * Each pattern names a category and a case-insensitive substring to look for in the path of a
+ * link, such as "/admin" or ".bak". Every http and https link found on a crawled page is checked,
+ * whether or not it is followed, and is flagged with the category of the first pattern it
+ * matches. The lists are leads for a closer look, not findings: /api matches /apiary as well.
+ *
+ * Extra patterns can be loaded from a file with one pattern per line in the form
+ * `Category | pattern`, checked after the built-in ones. Blank lines and lines starting with '#'
+ * are ignored.
+ */
+
+use std::{error::Error, fs};
+
+use reqwest::Url;
+
+pub struct Pattern {
+    category: String,
+    pattern: String,
+}
+
+const BUILTIN_PATTERNS: &[(&str, &str)] = &[
+    ("Admin", "/admin"),
+    ("Admin", "/wp-admin"),
+    ("Admin", "/phpmyadmin"),
+    ("Admin", "/cpanel"),
+    ("Admin", "/manager/html"),
+    ("Login", "/login"),
+    ("Login", "/signin"),
+    ("Login", "/sign-in"),
+    ("Login", "/logon"),
+    ("Login", "/wp-login.php"),
+    ("Login", "/auth/"),
+    ("Login", "/oauth"),
+    ("Login", "/sso"),
+    ("Source control", "/.git"),
+    ("Source control", "/.svn"),
+    ("Source control", "/.hg"),
+    ("Backup", "/backup"),
+    ("Backup", ".bak"),
+    ("Backup", ".old"),
+    ("Backup", ".sql"),
+    ("Backup", ".swp"),
+    ("Config", "/config"),
+    ("Config", "/.env"),
+    ("Config", "/.htaccess"),
+    ("Config", "/.htpasswd"),
+    ("Config", "web.config"),
+    ("Config", ".ini"),
+    ("API", "/api"),
+    ("API", "/graphql"),
+    ("API", "/swagger"),
+    ("API", "/openapi"),
+    ("Debug", "/phpinfo"),
+    ("Debug", "/server-status"),
+    ("Debug", "/actuator"),
+    ("Debug", "/debug"),
+];
+
+fn parse_pattern(category: &str, pattern: &str) -> Option<Pattern> {
+    let category = category.trim();
+    let pattern = pattern.trim().to_ascii_lowercase();
+    if category.is_empty() || pattern.is_empty() {
+        return None;
+    }
+    Some(Pattern {
+        category: category.to_string(),
+        pattern,
+    })
+}
+
+pub fn builtin_patterns() -> Vec<Pattern> {
+    BUILTIN_PATTERNS
+        .iter()
+        .filter_map(|(category, pattern)| parse_pattern(category, pattern))
+        .collect()
+}
+
+pub fn load_patterns(path: &str) -> Result<Vec<Pattern>, Box<dyn Error>> {
+    let mut patterns = Vec::new();
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let pattern = line
+            .split_once('|')
+            .and_then(|(category, pattern)| parse_pattern(category, pattern));
+        match pattern {
+            Some(pattern) => patterns.push(pattern),
+            None => return Err(format!("Invalid pattern on line {}: {}", number + 1, line).into()),
+        }
+    }
+    Ok(patterns)
+}
+
+pub fn category<'a>(patterns: &'a [Pattern], url: &Url) -> Option<&'a str> {
+    let path = url.path().to_ascii_lowercase();
+    patterns
+        .iter()
+        .find(|pattern| path.contains(&pattern.pattern))
+        .map(|pattern| pattern.category.as_str())
+}
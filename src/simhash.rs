@@ -0,0 +1,88 @@
+/*
+ * Near-duplicate page detection for --skip-similar.
+ *
+ * Every page gets a 64-bit SimHash fingerprint of its words. Each distinct word is hashed, and a
+ * bit of the fingerprint is set when the words whose hash has that bit set outweigh the words
+ * whose hash does not, with every word weighted by how often it occurs on the page. Pages made
+ * of mostly the same words get fingerprints that differ in only a few bits, so templated pages
+ * that differ in a product name or ID land within a few bits of each other, while unrelated
+ * pages differ in about half of the 64.
+ *
+ * The words are taken from the text of the page body, leaving out scripts and styles. A page is
+ * a near duplicate when its fingerprint differs in at most the threshold number of bits from a
+ * page already extracted; around 3 catches templated pages without merging distinct ones.
+ */
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use reqwest::Url;
+use select::{document::Document, predicate::Name};
+
+pub fn page_text(document: &Document) -> String {
+    let mut text = String::new();
+    let body = match document.find(Name("body")).next() {
+        Some(body) => body,
+        None => return text,
+    };
+    for node in body.descendants() {
+        let hidden = node
+            .parent()
+            .and_then(|parent| parent.name())
+            .is_some_and(|name| matches!(name, "script" | "style" | "noscript" | "template"));
+        if let Some(part) = node.as_text() {
+            if !hidden {
+                text.push_str(part);
+                text.push(' ');
+            }
+        }
+    }
+    text
+}
+
+pub fn fingerprint(words: &[String]) -> u64 {
+    let mut counts: HashMap<&str, i64> = HashMap::new();
+    for word in words {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    let mut weights = [0i64; 64];
+    for (word, count) in counts {
+        let mut hasher = DefaultHasher::new();
+        word.hash(&mut hasher);
+        let hash = hasher.finish();
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *weight += count;
+            } else {
+                *weight -= count;
+            }
+        }
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, &weight)| weight > 0)
+        .fold(0, |fingerprint, (bit, _)| fingerprint | (1 << bit))
+}
+
+#[derive(Default)]
+pub struct SeenPages {
+    fingerprints: Vec<(u64, Url)>,
+}
+
+impl SeenPages {
+    // The first page already seen within the threshold, or None after recording this one
+    pub fn check(&mut self, fingerprint: u64, url: &Url, threshold: u32) -> Option<Url> {
+        let similar = self
+            .fingerprints
+            .iter()
+            .find(|(seen, _)| (seen ^ fingerprint).count_ones() <= threshold)
+            .map(|(_, seen_url)| seen_url.clone());
+        if similar.is_none() {
+            self.fingerprints.push((fingerprint, url.clone()));
+        }
+        similar
+    }
+}
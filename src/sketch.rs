@@ -0,0 +1,81 @@
+/*
+ * Memory-bounded word counting for --approx-counts.
+ *
+ * Every occurrence is added to a Count-Min Sketch, DEPTH rows of WIDTH counters each indexed by a
+ * different hash of the word, and the estimate for a word is the smallest of its counters. Only
+ * the words with the highest estimates are kept by name, up to the working set size given on the
+ * command line; a word that was dropped and comes back picks up its estimate again, occurrences
+ * before it was dropped included.
+ *
+ * The sketch takes DEPTH * WIDTH * 4 bytes (16 MiB) however many distinct words the crawl finds.
+ * Estimates never undercount. They overcount by at most e / WIDTH of all words counted, about 3
+ * per million, for all but 1 in e^DEPTH (about 2%) of the words, since other words sharing a
+ * counter add to it. Rare words whose estimate never reaches the working set are lost, which
+ * does not matter for the top of the list but can for small counts near the minimum count.
+ */
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+const WIDTH: usize = 1 << 20;
+const DEPTH: usize = 4;
+
+pub struct ApproxCounter {
+    counters: Vec<u32>,
+    kept: HashMap<String, u32>,
+    capacity: usize,
+}
+
+impl ApproxCounter {
+    pub fn new(capacity: usize) -> ApproxCounter {
+        ApproxCounter {
+            counters: vec![0; DEPTH * WIDTH],
+            kept: HashMap::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    fn slots(word: &str) -> impl Iterator<Item = usize> + '_ {
+        (0..DEPTH).map(move |row| {
+            let mut hasher = DefaultHasher::new();
+            row.hash(&mut hasher);
+            word.hash(&mut hasher);
+            row * WIDTH + (hasher.finish() as usize) % WIDTH
+        })
+    }
+
+    pub fn add(&mut self, word: &str, count: u32) {
+        let mut estimate = u32::MAX;
+        for slot in ApproxCounter::slots(word) {
+            let counter = &mut self.counters[slot];
+            *counter = counter.saturating_add(count);
+            estimate = estimate.min(*counter);
+        }
+        match self.kept.get_mut(word) {
+            Some(kept) => *kept = estimate,
+            None => {
+                self.kept.insert(word.to_string(), estimate);
+            }
+        }
+        // Pruning only once the set has doubled keeps the cost per word constant
+        if self.kept.len() >= self.capacity * 2 {
+            self.prune();
+        }
+    }
+
+    fn prune(&mut self) {
+        let mut words: Vec<(String, u32)> = self.kept.drain().collect();
+        words.select_nth_unstable_by(self.capacity - 1, |a, b| b.1.cmp(&a.1));
+        words.truncate(self.capacity);
+        self.kept.extend(words);
+    }
+
+    pub fn into_counts(mut self) -> HashMap<String, u32> {
+        if self.kept.len() > self.capacity {
+            self.prune();
+        }
+        self.kept
+    }
+}
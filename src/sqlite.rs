@@ -0,0 +1,85 @@
+/*
+ * SQLite output for --sqlite.
+ *
+ * Word counts are stored per page in page_words, and the words view sums them into a total count
+ * and the number of pages each word is on, so the totals always match the pages stored. Crawling
+ * into an existing database updates it: the word counts of every page crawled again are replaced,
+ * pages that were not crawled this time keep their old counts, emails and socials found again
+ * are kept once, and each link keeps its latest status and the time it was last crawled.
+ */
+
+use std::collections::{HashMap, HashSet};
+
+use reqwest::Url;
+use rusqlite::{params, Connection};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS page_words (
+        url TEXT NOT NULL,
+        word TEXT NOT NULL,
+        count INTEGER NOT NULL,
+        PRIMARY KEY (url, word)
+    );
+    CREATE INDEX IF NOT EXISTS page_words_word ON page_words (word);
+    CREATE VIEW IF NOT EXISTS words AS
+        SELECT word, SUM(count) AS count, COUNT(*) AS pages FROM page_words GROUP BY word;
+    CREATE TABLE IF NOT EXISTS emails (
+        email TEXT NOT NULL,
+        source TEXT NOT NULL,
+        PRIMARY KEY (email, source)
+    );
+    CREATE TABLE IF NOT EXISTS socials (
+        account TEXT NOT NULL,
+        source TEXT NOT NULL,
+        PRIMARY KEY (account, source)
+    );
+    CREATE TABLE IF NOT EXISTS links (
+        url TEXT PRIMARY KEY,
+        status INTEGER NOT NULL,
+        crawled_at INTEGER NOT NULL
+    );
+";
+
+pub fn write(
+    path: &str,
+    pages: &[(&Url, &HashMap<String, u32>)],
+    emails: &HashMap<String, HashSet<Url>>,
+    socials: &HashMap<String, HashSet<Url>>,
+    links: &[(Url, u16)],
+) -> rusqlite::Result<()> {
+    let mut connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    let transaction = connection.transaction()?;
+
+    for (url, words) in pages {
+        transaction.execute("DELETE FROM page_words WHERE url = ?1", [url.as_str()])?;
+        let mut insert = transaction
+            .prepare_cached("INSERT INTO page_words (url, word, count) VALUES (?1, ?2, ?3)")?;
+        for (word, count) in words.iter() {
+            insert.execute(params![url.as_str(), word, count])?;
+        }
+    }
+
+    for (table, column, found) in [("emails", "email", emails), ("socials", "account", socials)] {
+        let mut insert = transaction.prepare(&format!(
+            "INSERT OR IGNORE INTO {} ({}, source) VALUES (?1, ?2)",
+            table, column
+        ))?;
+        for (value, sources) in found {
+            for source in sources {
+                insert.execute(params![value, source.as_str()])?;
+            }
+        }
+    }
+
+    let mut upsert = transaction.prepare(
+        "INSERT INTO links (url, status, crawled_at) VALUES (?1, ?2, unixepoch())
+         ON CONFLICT (url) DO UPDATE SET status = excluded.status, crawled_at = excluded.crawled_at",
+    )?;
+    for (url, status) in links {
+        upsert.execute(params![url.as_str(), status])?;
+    }
+    drop(upsert);
+
+    transaction.commit()
+}
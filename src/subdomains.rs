@@ -0,0 +1,42 @@
+/*
+ * Collection of subdomains of the seed's registrable domain, used by --subdomains.
+ *
+ * Hosts are taken from every URL a page refers to: links, script, image and frame sources, Open
+ * Graph URLs and JSON-LD sameAs links, as well as the page itself. Inline scripts are not parsed,
+ * they are searched for hostnames ending in the domain instead, which catches API and CDN hosts
+ * that only appear in code. The registrable domain itself is not a subdomain and is left out.
+ */
+
+use regex::Regex;
+
+pub struct SubdomainMatcher {
+    domain: String,
+    hostname: Regex,
+}
+
+impl SubdomainMatcher {
+    pub fn new(domain: &str) -> SubdomainMatcher {
+        let domain = domain.to_lowercase();
+        let hostname = Regex::new(&format!(
+            r"(?i)\b(?:[a-z0-9](?:[a-z0-9-]*[a-z0-9])?\.)+{}\b",
+            regex::escape(&domain)
+        ))
+        .unwrap();
+        SubdomainMatcher { domain, hostname }
+    }
+
+    pub fn subdomain(&self, host: &str) -> Option<String> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let is_subdomain = host
+            .strip_suffix(&self.domain)
+            .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.'));
+        is_subdomain.then_some(host)
+    }
+
+    pub fn find(&self, text: &str) -> Vec<String> {
+        self.hostname
+            .find_iter(text)
+            .filter_map(|host| self.subdomain(host.as_str()))
+            .collect()
+    }
+}
@@ -0,0 +1,166 @@
+/*
+ * Technology fingerprinting from response headers and markup.
+ *
+ * Each signature names a technology and a case-insensitive substring to look for in one place:
+ * a response header, the name of a cookie set by the server, or the href/src of an asset or link
+ * on the page. The <meta name="generator"> tag is reported verbatim, as it usually names the
+ * product and version.
+ *
+ * Extra signatures can be loaded from a file with one signature per line in the form
+ * `Technology | location | pattern`, where location is `header:<name>`, `cookie` or `path`.
+ * Blank lines and lines starting with '#' are ignored.
+ */
+
+use std::{error::Error, fs};
+
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use select::{document::Document, predicate::Name};
+
+enum Location {
+    Header(String),
+    Cookie,
+    AssetPath,
+}
+
+pub struct Signature {
+    technology: String,
+    location: Location,
+    pattern: String,
+}
+
+const BUILTIN_SIGNATURES: &[(&str, &str, &str)] = &[
+    ("WordPress", "path", "/wp-content/"),
+    ("WordPress", "path", "/wp-includes/"),
+    ("WordPress", "cookie", "wordpress_"),
+    ("Drupal", "path", "/sites/default/files/"),
+    ("Joomla", "path", "/media/jui/"),
+    ("Next.js", "path", "/_next/"),
+    ("Nuxt.js", "path", "/_nuxt/"),
+    ("Shopify", "path", "cdn.shopify.com"),
+    ("Shopify", "cookie", "_shopify_"),
+    ("Laravel", "cookie", "laravel_session"),
+    ("Django", "cookie", "csrftoken"),
+    ("PHP", "header:x-powered-by", "php"),
+    ("PHP", "cookie", "phpsessid"),
+    ("Java", "cookie", "jsessionid"),
+    ("ASP.NET", "header:x-powered-by", "asp.net"),
+    ("ASP.NET", "header:x-aspnet-version", ""),
+    ("ASP.NET", "cookie", "asp.net_sessionid"),
+    ("Express", "header:x-powered-by", "express"),
+    ("nginx", "header:server", "nginx"),
+    ("Apache", "header:server", "apache"),
+    ("Microsoft IIS", "header:server", "microsoft-iis"),
+    ("Cloudflare", "header:server", "cloudflare"),
+];
+
+fn parse_signature(technology: &str, location: &str, pattern: &str) -> Option<Signature> {
+    let location = match location.trim().to_ascii_lowercase().as_str() {
+        "cookie" => Location::Cookie,
+        "path" => Location::AssetPath,
+        other => Location::Header(other.strip_prefix("header:")?.trim().to_string()),
+    };
+    Some(Signature {
+        technology: technology.trim().to_string(),
+        location,
+        pattern: pattern.trim().to_ascii_lowercase(),
+    })
+}
+
+pub fn builtin_signatures() -> Vec<Signature> {
+    BUILTIN_SIGNATURES
+        .iter()
+        .filter_map(|(technology, location, pattern)| {
+            parse_signature(technology, location, pattern)
+        })
+        .collect()
+}
+
+pub fn load_signatures(path: &str) -> Result<Vec<Signature>, Box<dyn Error>> {
+    let mut signatures = Vec::new();
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.splitn(3, '|').collect();
+        let signature = match parts.as_slice() {
+            [technology, location, pattern] => parse_signature(technology, location, pattern),
+            _ => None,
+        };
+        match signature {
+            Some(signature) => signatures.push(signature),
+            None => {
+                return Err(format!("Invalid signature on line {}: {}", number + 1, line).into())
+            }
+        }
+    }
+    Ok(signatures)
+}
+
+pub struct Detection {
+    pub technology: String,
+    pub evidence: String,
+}
+
+pub fn detect(
+    signatures: &[Signature],
+    headers: &HeaderMap,
+    document: &Document,
+) -> Vec<Detection> {
+    let mut detections = Vec::new();
+
+    for meta in document.find(Name("meta")) {
+        let is_generator = meta
+            .attr("name")
+            .is_some_and(|name| name.eq_ignore_ascii_case("generator"));
+        if let (true, Some(content)) = (is_generator, meta.attr("content")) {
+            detections.push(Detection {
+                technology: content.trim().to_string(),
+                evidence: format!("<meta name=\"generator\" content=\"{}\">", content.trim()),
+            });
+        }
+    }
+
+    let asset_paths: Vec<&str> = document
+        .find(Name("a"))
+        .chain(document.find(Name("link")))
+        .chain(document.find(Name("script")))
+        .chain(document.find(Name("img")))
+        .filter_map(|node| node.attr("href").or_else(|| node.attr("src")))
+        .collect();
+
+    let cookie_names: Vec<&str> = headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|cookie| cookie.split('=').next())
+        .map(str::trim)
+        .collect();
+
+    for signature in signatures {
+        let pattern = signature.pattern.as_str();
+        let evidence = match &signature.location {
+            Location::Header(name) => headers
+                .get(name.as_str())
+                .and_then(|value| value.to_str().ok())
+                .filter(|value| value.to_ascii_lowercase().contains(pattern))
+                .map(|value| format!("{}: {}", name, value)),
+            Location::Cookie => cookie_names
+                .iter()
+                .find(|cookie| cookie.to_ascii_lowercase().contains(pattern))
+                .map(|cookie| format!("cookie {}", cookie)),
+            Location::AssetPath => asset_paths
+                .iter()
+                .find(|path| path.to_ascii_lowercase().contains(pattern))
+                .map(|path| format!("asset {}", path)),
+        };
+        if let Some(evidence) = evidence {
+            detections.push(Detection {
+                technology: signature.technology.clone(),
+                evidence,
+            });
+        }
+    }
+
+    detections
+}
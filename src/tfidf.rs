@@ -0,0 +1,50 @@
+/*
+ * TF-IDF scoring of words against a background corpus, used by --rank tfidf.
+ *
+ * The background is the bundled common words list, which is ordered from most to least frequent
+ * English word. Word frequencies roughly follow Zipf's law, where a word's frequency is
+ * proportional to 1 / rank, so the inverse document frequency is taken as ln(1 + rank):
+ *
+ *     score = count * ln(1 + rank)
+ *
+ * Words that are not in the list are rarer than any that are and get the rank just past its end,
+ * which makes jargon that is frequent on the crawled site rise to the top. If the list cannot be
+ * read every word gets that same rank, and the order falls back to plain counts.
+ */
+
+use std::{collections::HashMap, fs};
+
+const BACKGROUND_PATH: &str = "src/resources/commonwords.txt";
+
+pub fn background_ranks() -> HashMap<String, usize> {
+    match fs::read_to_string(BACKGROUND_PATH) {
+        Ok(list) => list
+            .lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .enumerate()
+            .map(|(rank, word)| (word.to_lowercase(), rank + 1))
+            .collect(),
+        Err(err) => {
+            eprintln!(
+                "Warning: Unable to read background word list '{}', ranking by count: {}",
+                BACKGROUND_PATH, err
+            );
+            HashMap::new()
+        }
+    }
+}
+
+pub fn scores(
+    word_count: &HashMap<String, u32>,
+    ranks: &HashMap<String, usize>,
+) -> HashMap<String, f64> {
+    let unseen_rank = ranks.len() + 1;
+    word_count
+        .iter()
+        .map(|(word, &count)| {
+            let rank = ranks.get(word).copied().unwrap_or(unseen_rank);
+            (word.clone(), count as f64 * (1.0 + rank as f64).ln())
+        })
+        .collect()
+}
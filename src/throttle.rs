@@ -0,0 +1,226 @@
+/*
+ * Per-host request pacing.
+ *
+ * Each host keeps a minimum delay between requests, plus an optional random jitter of up to the
+ * given amount added to every wait, and a window of its most recent responses.
+ * When block detection is enabled and at least half of the last few responses look like blocks
+ * (403, 429, 503 or a Cloudflare challenge), the delay for that host is doubled, starting at one
+ * second. Once it would exceed a minute the host is paused and no further requests are sent to it.
+ *
+ * A host can also be held for a given time, as asked for by a Retry-After header, after which
+ * requests to it resume at the usual pace. Holds longer than MAX_HOLD pause the host instead.
+ *
+ * On top of the per-host pacing, a token bucket can cap the rate of requests across all hosts.
+ * It holds up to one second's worth of tokens and refills continuously with the time elapsed, so
+ * short bursts go out at once while the average stays at the given rate. Every request takes a
+ * token and waits for the next one when the bucket is empty.
+ *
+ * Only idempotent requests (GET, HEAD and the like) are sent again after a Retry-After pause,
+ * since repeating a POST can repeat whatever it did, such as a second login or form submission.
+ * The retry policy can be told to retry those as well.
+ *
+ * Each host also keeps an audit of how it was paced: the requests sent, the shortest gap between
+ * two of them, and how often block backoff and Retry-After holds slowed it down.
+ */
+
+use std::{
+    collections::VecDeque,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use rand::Rng;
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    Method, StatusCode,
+};
+
+const WINDOW_SIZE: usize = 20;
+const MIN_SAMPLES: usize = 5;
+const MAX_DELAY: Duration = Duration::from_secs(60);
+pub const MAX_HOLD: Duration = Duration::from_secs(300);
+
+pub enum BlockAction {
+    Backoff(Duration),
+    Pause,
+}
+
+#[derive(Default)]
+pub struct HostAudit {
+    pub requests: u32,
+    pub min_gap: Option<Duration>,
+    pub backoffs: u32,
+    pub holds: u32,
+    pub held: Duration,
+}
+
+pub struct HostThrottle {
+    pub delay: Duration,
+    pub paused: bool,
+    pub audit: HostAudit,
+    jitter: Duration,
+    recent: VecDeque<bool>,
+    last_request: Option<Instant>,
+    resume_at: Option<Instant>,
+}
+
+impl HostThrottle {
+    pub fn new(delay: Duration, jitter: Duration) -> HostThrottle {
+        HostThrottle {
+            delay,
+            paused: false,
+            audit: HostAudit::default(),
+            jitter,
+            recent: VecDeque::new(),
+            last_request: None,
+            resume_at: None,
+        }
+    }
+
+    pub fn wait(&mut self) {
+        if let Some(resume_at) = self.resume_at.take() {
+            let now = Instant::now();
+            if resume_at > now {
+                thread::sleep(resume_at - now);
+            }
+        }
+        if let Some(last_request) = self.last_request {
+            let mut target = self.delay;
+            if !self.jitter.is_zero() {
+                target += rand::thread_rng().gen_range(Duration::ZERO..=self.jitter);
+            }
+            let elapsed = last_request.elapsed();
+            if elapsed < target {
+                thread::sleep(target - elapsed);
+            }
+            let gap = last_request.elapsed();
+            self.audit.min_gap = Some(self.audit.min_gap.map_or(gap, |min_gap| min_gap.min(gap)));
+        }
+        self.audit.requests += 1;
+        self.last_request = Some(Instant::now());
+    }
+
+    pub fn record(&mut self, blocked: bool) -> Option<BlockAction> {
+        if self.recent.len() == WINDOW_SIZE {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(blocked);
+
+        let blocked_count = self.recent.iter().filter(|&&b| b).count();
+        if self.recent.len() < MIN_SAMPLES || blocked_count * 2 < self.recent.len() {
+            return None;
+        }
+
+        // Start a fresh window so the new delay is judged on its own responses
+        self.recent.clear();
+        self.audit.backoffs += 1;
+        self.delay = if self.delay.is_zero() {
+            Duration::from_secs(1)
+        } else {
+            self.delay * 2
+        };
+        if self.delay > MAX_DELAY {
+            self.paused = true;
+            Some(BlockAction::Pause)
+        } else {
+            Some(BlockAction::Backoff(self.delay))
+        }
+    }
+
+    // Returns false when the hold is too long and the host has been paused instead
+    pub fn hold(&mut self, duration: Duration) -> bool {
+        if duration > MAX_HOLD {
+            self.paused = true;
+            return false;
+        }
+        self.resume_at = Some(Instant::now() + duration);
+        self.audit.holds += 1;
+        self.audit.held += duration;
+        true
+    }
+}
+
+pub struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64) -> TokenBucket {
+        let capacity = rate.max(1.0);
+        TokenBucket {
+            rate,
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    pub fn take(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - self.tokens) / self.rate);
+            thread::sleep(wait);
+            self.last_refill += wait;
+            self.tokens = 1.0;
+        }
+        self.tokens -= 1.0;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct RetryPolicy {
+    pub respect_retry_after: bool,
+    pub non_idempotent: bool,
+}
+
+impl RetryPolicy {
+    // How long to wait before sending a request again, None when it is not to be retried
+    pub fn retry_delay(
+        &self,
+        method: &Method,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<Duration> {
+        if !self.respect_retry_after || !(method.is_idempotent() || self.non_idempotent) {
+            return None;
+        }
+        retry_after(status, headers)
+    }
+}
+
+// Retry-After is either a number of seconds or an HTTP date, and only honoured on 429 and 503
+fn retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if !matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    ) {
+        return None;
+    }
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            let date = httpdate::parse_http_date(value).ok()?;
+            Some(
+                date.duration_since(SystemTime::now())
+                    .unwrap_or(Duration::ZERO),
+            )
+        }
+    }
+}
+
+pub fn looks_blocked(status: StatusCode, headers: &HeaderMap) -> bool {
+    matches!(
+        status,
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    ) || headers
+        .get("cf-mitigated")
+        .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"challenge"))
+}
@@ -0,0 +1,410 @@
+/*
+ * Splitting page text into candidate words.
+ *
+ * By default text is split on Unicode word boundaries (UAX #29) and a word is kept when every
+ * character is a letter, a combining mark or an apostrophe, so words in any script are captured
+ * and punctuation next to a word no longer discards it. Chinese and Japanese are written without
+ * spaces, which this algorithm would cut into single characters, so runs of Han, Hiragana or
+ * Katakana are kept whole instead, splitting only where the script changes: "東京都に住む"
+ * gives "東京都", "に", "住" and "む". This is no dictionary segmentation, a Chinese sentence
+ * stays one word, but compounds written in one script come out as they are. Their characters
+ * count double against the minimum word length, as they take two columns and carry about as
+ * much as two Latin letters, so "東京" meets a minimum of 4.
+ *
+ * In ASCII-only mode the original behaviour is kept: text is split on whitespace and any word
+ * containing something other than a-z or an apostrophe is dropped.
+ *
+ * A custom word pattern replaces both: every match of it is a word, or the text of its first
+ * capture group when the pattern has one and it took part in the match. Custom words are only
+ * lowercased and held to the minimum letter ratio.
+ *
+ * Every word must be made of at least a minimum fraction of letters, so tokens that are mostly
+ * digits or symbols, such as "a1b2c3d4" or "x86_64", are dropped. The default of 0.6 lets a
+ * word like "html5" through while rejecting half-digit ones. The built-in splitting only keeps
+ * letters and apostrophes, so in practice the ratio filters custom word patterns.
+ *
+ * Apostrophes are kept by default, so "don't" and "o'brien" are words of their own. They can be
+ * stripped instead, making "dont" and "obrien", or contractions can be taken apart. Splitting
+ * cuts a word before its ending (n't, 's, 're, 've, 'll, 'd or 'm) and drops the apostrophe, so
+ * "don't" gives "do" and "nt" and "it's" gives "it" and "s". Expanding replaces the ending with
+ * the word it stands for, so "don't" gives "do" and "not". Either way the few negations whose
+ * first word is spelled differently get it back, so "can't" gives "can" and "won't" gives "will"
+ * rather than "ca" and "wo".
+ * Expansion cannot tell the meanings of 'd and 's apart: 'd always becomes "would", 's becomes
+ * "is" after the pronouns in IS_CONTRACTIONS and is dropped as a possessive after anything else.
+ * Words without a known ending, such as "o'brien", are left as they are, and neither applies to
+ * custom word patterns.
+ *
+ * Page text is brought into one Unicode normalization form before it is split, NFC unless
+ * another form is chosen.
+ *
+ * An element's text is read in chunks of about CHUNK_SIZE bytes instead of being collected into
+ * one string first, so a page made of a few huge text nodes does not need a second copy of its
+ * whole text in memory. Chunks end on whitespace, where words are split anyway, so the words
+ * found are the same as for the whole text. Only a run of over CHUNK_SIZE bytes without
+ * whitespace is cut in the middle.
+ */
+
+use std::mem;
+
+use clap::ValueEnum;
+use regex::Regex;
+use select::node::{Descendants, Node};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NormalizationForm {
+    /// Composed characters, e.g. "é" as one character
+    Nfc,
+    /// Decomposed characters, e.g. "é" as "e" plus a combining accent
+    Nfd,
+    /// Composed, with compatibility characters folded, e.g. "ﬁ" to "fi" and "Ａ" to "A"
+    Nfkc,
+    /// Decomposed, with compatibility characters folded
+    Nfkd,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Contractions {
+    /// Split at the apostrophe, e.g. "don't" to "do" and "nt"
+    Split,
+    /// Replace with the words they stand for, e.g. "don't" to "do" and "not"
+    Expand,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Apostrophes {
+    Keep,
+    Strip,
+    Contractions(Contractions),
+}
+
+// Endings in the order they are tried, with the word each stands for
+const CONTRACTIONS: &[(&str, &str)] = &[
+    ("n't", "not"),
+    ("'re", "are"),
+    ("'ve", "have"),
+    ("'ll", "will"),
+    ("'d", "would"),
+    ("'m", "am"),
+    ("'s", "is"),
+];
+
+// Negations whose first word is not what is left in front of n't
+const IRREGULAR_NEGATIONS: &[(&str, &str)] = &[
+    ("can't", "can"),
+    ("won't", "will"),
+    ("shan't", "shall"),
+    ("ain't", "is"),
+];
+
+const IS_CONTRACTIONS: &[&str] = &[
+    "it", "he", "she", "that", "what", "there", "here", "who", "where", "how",
+];
+
+pub const DEFAULT_MIN_ALPHA_RATIO: f64 = 0.6;
+
+pub struct Tokenizer {
+    ascii_only: bool,
+    form: NormalizationForm,
+    min_alpha_ratio: f64,
+    apostrophes: Apostrophes,
+    invalid: Regex,
+    cjk_runs: Regex,
+    word_regex: Option<Regex>,
+}
+
+// Ideographs and kana, which take two columns; the iteration and prolonged sound marks belong to
+// no script of their own but are written inside these runs
+fn is_wide(c: char) -> bool {
+    matches!(c,
+        '\u{3005}'
+        | '\u{3040}'..='\u{30ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{20000}'..='\u{3ffff}')
+}
+
+// Length held against the minimum word length
+pub fn word_length(word: &str) -> usize {
+    word.chars().map(|c| if is_wide(c) { 2 } else { 1 }).sum()
+}
+
+impl Tokenizer {
+    pub fn new(
+        ascii_only: bool,
+        form: NormalizationForm,
+        min_alpha_ratio: f64,
+        apostrophes: Apostrophes,
+        word_regex: Option<Regex>,
+    ) -> Tokenizer {
+        let invalid = if ascii_only {
+            r"[^a-zA-Z']+"
+        } else {
+            r"[^\p{L}\p{M}']+"
+        };
+        Tokenizer {
+            ascii_only,
+            form,
+            min_alpha_ratio,
+            apostrophes,
+            invalid: Regex::new(invalid).unwrap(),
+            cjk_runs: Regex::new(r"[\p{Han}\u{3005}]+|\p{Hiragana}+|[\p{Katakana}\u{30fc}]+")
+                .unwrap(),
+            word_regex,
+        }
+    }
+
+    pub fn normalize(&self, text: &str) -> String {
+        match self.form {
+            NormalizationForm::Nfc => text.nfc().collect(),
+            NormalizationForm::Nfd => text.nfd().collect(),
+            NormalizationForm::Nfkc => text.nfkc().collect(),
+            NormalizationForm::Nfkd => text.nfkd().collect(),
+        }
+    }
+
+    pub fn words(&self, text: &str) -> Vec<String> {
+        if let Some(ref word_regex) = self.word_regex {
+            return word_regex
+                .captures_iter(text)
+                .filter_map(|captures| captures.get(1).or_else(|| captures.get(0)))
+                .map(|word| word.as_str().to_lowercase())
+                .filter(|word| !word.is_empty() && self.mostly_letters(word))
+                .collect();
+        }
+
+        let candidates: Vec<&str> = if self.ascii_only {
+            text.split_whitespace().collect()
+        } else {
+            let mut candidates = Vec::new();
+            let mut rest = 0;
+            for run in self.cjk_runs.find_iter(text) {
+                candidates.extend(text[rest..run.start()].unicode_words());
+                candidates.push(run.as_str());
+                rest = run.end();
+            }
+            candidates.extend(text[rest..].unicode_words());
+            candidates
+        };
+
+        candidates
+            .into_iter()
+            .map(|word| {
+                let word = word.to_lowercase();
+                // Typographic apostrophes are folded so "don’t" and "don't" count as one word
+                if self.ascii_only {
+                    word
+                } else {
+                    word.replace('\u{2019}', "'")
+                }
+            })
+            .filter(|word| {
+                !word.is_empty() && !self.invalid.is_match(word) && self.mostly_letters(word)
+            })
+            .flat_map(|word| self.treat_apostrophes(word))
+            .collect()
+    }
+
+    fn treat_apostrophes(&self, word: String) -> Vec<String> {
+        if !word.contains('\'') {
+            return vec![word];
+        }
+        let contractions = match self.apostrophes {
+            Apostrophes::Keep => return vec![word],
+            Apostrophes::Strip => return vec![word.replace('\'', "")],
+            Apostrophes::Contractions(contractions) => contractions,
+        };
+        let contraction = CONTRACTIONS
+            .iter()
+            .find(|(ending, _)| word.len() > ending.len() && word.ends_with(ending));
+        let (ending, expansion) = match contraction {
+            Some(&contraction) => contraction,
+            None => return vec![word],
+        };
+        let stem = &word[..word.len() - ending.len()];
+        let rest = match contractions {
+            Contractions::Split => ending.replace('\'', ""),
+            Contractions::Expand if ending == "'s" && !IS_CONTRACTIONS.contains(&stem) => {
+                return vec![stem.to_string()];
+            }
+            Contractions::Expand => expansion.to_string(),
+        };
+        let stem = IRREGULAR_NEGATIONS
+            .iter()
+            .find(|(negation, _)| *negation == word)
+            .map_or(stem, |(_, stem)| stem);
+        vec![stem.to_string(), rest]
+    }
+
+    fn mostly_letters(&self, word: &str) -> bool {
+        let total = word.chars().count();
+        let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+        letters as f64 >= self.min_alpha_ratio * total as f64
+    }
+}
+
+pub struct TextChunks<'a> {
+    descendants: Descendants<'a>,
+    pending: &'a str,
+    buffer: String,
+}
+
+impl<'a> TextChunks<'a> {
+    pub fn new(node: &Node<'a>) -> TextChunks<'a> {
+        TextChunks {
+            descendants: node.descendants(),
+            pending: "",
+            buffer: String::new(),
+        }
+    }
+}
+
+impl<'a> Iterator for TextChunks<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            if self.buffer.len() >= CHUNK_SIZE {
+                let split = self
+                    .buffer
+                    .char_indices()
+                    .rev()
+                    .find(|(_, c)| c.is_whitespace())
+                    .map_or(self.buffer.len(), |(i, c)| i + c.len_utf8());
+                let rest = self.buffer.split_off(split);
+                return Some(mem::replace(&mut self.buffer, rest));
+            }
+            if self.pending.is_empty() {
+                match self.descendants.find_map(|node| node.as_text()) {
+                    Some(text) => self.pending = text,
+                    None if self.buffer.is_empty() => return None,
+                    None => return Some(mem::take(&mut self.buffer)),
+                }
+            }
+            // Text nodes are copied a chunk at a time, never whole
+            let mut end = (CHUNK_SIZE - self.buffer.len()).min(self.pending.len());
+            while !self.pending.is_char_boundary(end) {
+                end += 1;
+            }
+            self.buffer.push_str(&self.pending[..end]);
+            self.pending = &self.pending[end..];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokenizer() -> Tokenizer {
+        Tokenizer::new(
+            false,
+            NormalizationForm::Nfc,
+            DEFAULT_MIN_ALPHA_RATIO,
+            Apostrophes::Keep,
+            None,
+        )
+    }
+
+    #[test]
+    fn latin_words_keep_accents_and_lose_punctuation() {
+        assert_eq!(
+            tokenizer().words("Café, naïve (déjà-vu) don't stop!"),
+            ["café", "naïve", "déjà", "vu", "don't", "stop"]
+        );
+    }
+
+    #[test]
+    fn cyrillic_words_are_split_on_spaces() {
+        assert_eq!(
+            tokenizer().words("Привет, мир! Съешь же ещё"),
+            ["привет", "мир", "съешь", "же", "ещё"]
+        );
+    }
+
+    #[test]
+    fn arabic_words_are_split_on_spaces() {
+        assert_eq!(
+            tokenizer().words("مرحبا بالعالم، كيف الحال؟"),
+            ["مرحبا", "بالعالم", "كيف", "الحال"]
+        );
+    }
+
+    #[test]
+    fn cjk_runs_are_kept_whole() {
+        assert_eq!(
+            tokenizer().words("東京都に住む。コーヒーを飲む"),
+            ["東京都", "に", "住", "む", "コーヒー", "を", "飲", "む"]
+        );
+        assert_eq!(tokenizer().words("北京欢迎你"), ["北京欢迎你"]);
+    }
+
+    #[test]
+    fn cjk_characters_count_double_towards_the_length() {
+        assert_eq!(word_length("東京"), 4);
+        assert_eq!(word_length("コーヒー"), 8);
+        assert_eq!(word_length("café"), 4);
+    }
+
+    fn with_apostrophes(apostrophes: Apostrophes) -> Tokenizer {
+        Tokenizer::new(
+            false,
+            NormalizationForm::Nfc,
+            DEFAULT_MIN_ALPHA_RATIO,
+            apostrophes,
+            None,
+        )
+    }
+
+    #[test]
+    fn apostrophes_are_stripped() {
+        assert_eq!(
+            with_apostrophes(Apostrophes::Strip).words("don't o'brien"),
+            ["dont", "obrien"]
+        );
+    }
+
+    #[test]
+    fn contractions_are_split() {
+        assert_eq!(
+            with_apostrophes(Apostrophes::Contractions(Contractions::Split))
+                .words("don't it's we're o'brien"),
+            ["do", "nt", "it", "s", "we", "re", "o'brien"]
+        );
+    }
+
+    #[test]
+    fn irregular_negations_are_split_on_their_stem() {
+        assert_eq!(
+            with_apostrophes(Apostrophes::Contractions(Contractions::Split))
+                .words("can't won't shan't"),
+            ["can", "nt", "will", "nt", "shall", "nt"]
+        );
+    }
+
+    #[test]
+    fn contractions_are_expanded() {
+        assert_eq!(
+            with_apostrophes(Apostrophes::Contractions(Contractions::Expand))
+                .words("don't can't won't it's john's i'd"),
+            ["do", "not", "can", "not", "will", "not", "it", "is", "john", "i", "would"]
+        );
+    }
+
+    #[test]
+    fn ascii_only_drops_other_scripts() {
+        let tokenizer = Tokenizer::new(
+            true,
+            NormalizationForm::Nfc,
+            DEFAULT_MIN_ALPHA_RATIO,
+            Apostrophes::Keep,
+            None,
+        );
+        assert_eq!(tokenizer.words("plain café мир words"), ["plain", "words"]);
+    }
+}
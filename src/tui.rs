@@ -0,0 +1,210 @@
+/*
+ * Live dashboard of the crawl for --tui.
+ *
+ * The crawl is synchronous, so the dashboard is redrawn from the crawl itself after every page,
+ * at most every REDRAW_INTERVAL, rather than from a thread of its own; it stands still while a
+ * slow page is being fetched. It shows the totals so far with the rate of pages per second, the
+ * pages fetched from each host with how many failed, the top words so far and the most recently
+ * fetched URLs with their status. A host whose rows fill up with 403 and 429 is being blocked.
+ *
+ * Pressing q (or Ctrl-C, which raw mode turns into a key press) stops the crawl after the current
+ * page, and the results so far are written as usual once the terminal has been restored.
+ * Warnings printed during the crawl go to stderr and would be drawn over, so redirect it to a
+ * file to keep them.
+ */
+
+use std::{
+    collections::HashMap,
+    io,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+use reqwest::Url;
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+const TOP_WORDS: usize = 20;
+
+pub struct Snapshot<'a> {
+    pub depth: u32,
+    pub parsed: usize,
+    pub errors: usize,
+    pub fetched: &'a [(Url, u16)],
+}
+
+pub struct Dashboard {
+    terminal: DefaultTerminal,
+    started: Instant,
+    drawn: Option<Instant>,
+    words: HashMap<String, u32>,
+    pub quit: bool,
+}
+
+impl Dashboard {
+    pub fn start() -> io::Result<Dashboard> {
+        Ok(Dashboard {
+            terminal: ratatui::try_init()?,
+            started: Instant::now(),
+            drawn: None,
+            words: HashMap::new(),
+            quit: false,
+        })
+    }
+
+    pub fn add_words(&mut self, words: &HashMap<String, u32>) {
+        for (word, count) in words {
+            *self.words.entry(word.clone()).or_insert(0) += count;
+        }
+    }
+
+    pub fn update(&mut self, snapshot: &Snapshot) {
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            if let Ok(Event::Key(key)) = event::read() {
+                let ctrl_c =
+                    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.code == KeyCode::Char('q') || ctrl_c {
+                    self.quit = true;
+                }
+            }
+        }
+        if self
+            .drawn
+            .is_some_and(|drawn| drawn.elapsed() < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        self.drawn = Some(Instant::now());
+
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let mut words: Vec<(&String, &u32)> = self.words.iter().collect();
+        words.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        words.truncate(TOP_WORDS);
+        let quit = self.quit;
+        let _ = self
+            .terminal
+            .draw(|frame| draw(frame, snapshot, elapsed, &words, quit));
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+// Pages fetched and failed for each host, busiest first
+fn hosts(fetched: &[(Url, u16)]) -> Vec<(String, usize, usize)> {
+    let mut hosts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (url, status) in fetched {
+        let host = hosts.entry(url.host_str().unwrap_or_default()).or_default();
+        host.0 += 1;
+        if *status >= 400 {
+            host.1 += 1;
+        }
+    }
+    let mut hosts: Vec<(String, usize, usize)> = hosts
+        .into_iter()
+        .map(|(host, (fetched, failed))| (host.to_string(), fetched, failed))
+        .collect();
+    hosts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    hosts
+}
+
+fn draw(
+    frame: &mut Frame,
+    snapshot: &Snapshot,
+    elapsed: f64,
+    words: &[(&String, &u32)],
+    quit: bool,
+) {
+    let [summary, middle, log] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Percentage(50),
+        Constraint::Fill(1),
+    ])
+    .areas(frame.area());
+    let [hosts_area, words_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(middle);
+
+    let fetched = snapshot.fetched.len();
+    let rate = if elapsed > 0.0 {
+        fetched as f64 / elapsed
+    } else {
+        0.0
+    };
+    let status = if quit {
+        "Stopping after the current page".yellow()
+    } else {
+        "Press q to stop the crawl and write the results".dim()
+    };
+    let totals = vec![
+        Line::from(format!(
+            "Fetched {}  Parsed {}  Errors {}  Depth {}  {:.1} pages/s  {:.0}s",
+            fetched, snapshot.parsed, snapshot.errors, snapshot.depth, rate, elapsed
+        )),
+        Line::from(status),
+    ];
+    frame.render_widget(
+        Paragraph::new(totals).block(Block::bordered().title(" harvest ")),
+        summary,
+    );
+
+    let rows = hosts(snapshot.fetched)
+        .into_iter()
+        .map(|(host, fetched, failed)| {
+            let row = Row::new(vec![host, fetched.to_string(), failed.to_string()]);
+            if failed * 2 > fetched {
+                row.style(Style::default().fg(Color::Red))
+            } else {
+                row
+            }
+        });
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(8),
+            ],
+        )
+        .header(Row::new(vec!["Host", "Fetched", "Failed"]).bold())
+        .block(Block::bordered().title(" Hosts ")),
+        hosts_area,
+    );
+
+    let rows = words
+        .iter()
+        .map(|(word, count)| Row::new(vec![word.to_string(), count.to_string()]));
+    frame.render_widget(
+        Table::new(rows, [Constraint::Fill(1), Constraint::Length(8)])
+            .block(Block::bordered().title(" Top words ")),
+        words_area,
+    );
+
+    // Newest last, as many as fit inside the border
+    let height = log.height.saturating_sub(2) as usize;
+    let start = snapshot.fetched.len().saturating_sub(height);
+    let lines: Vec<Line> = snapshot.fetched[start..]
+        .iter()
+        .map(|(url, status)| {
+            let line = Line::from(format!("{} {}", status, url));
+            if *status >= 400 {
+                line.red()
+            } else {
+                line
+            }
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(" Fetched ")),
+        log,
+    );
+}
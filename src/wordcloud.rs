@@ -0,0 +1,50 @@
+/*
+ * Word cloud export for --wordcloud.
+ *
+ * The DEFAULT_WORDS highest ranked words, or as many as --top asks for, are written with a size
+ * from 1 to MAX_SIZE, in proportion to their count, or to their score when ranking by something
+ * other than count, so the top word always gets MAX_SIZE. Files ending in .json (or .json.gz)
+ * get an array of {"text", "size"} objects, the shape d3-cloud takes; any other file gets
+ * "word,size" lines.
+ */
+
+use serde_json::json;
+
+pub const DEFAULT_WORDS: usize = 100;
+const MAX_SIZE: f64 = 100.0;
+
+fn sizes(words: &[(&String, f64)], limit: usize) -> Vec<(String, u32)> {
+    let top = words.iter().map(|(_, weight)| *weight).fold(0.0, f64::max);
+    words
+        .iter()
+        .take(limit)
+        .map(|(word, weight)| {
+            let size = if top > 0.0 {
+                (weight / top * MAX_SIZE).round().max(1.0)
+            } else {
+                1.0
+            };
+            (word.to_string(), size as u32)
+        })
+        .collect()
+}
+
+pub fn is_json(path: &str) -> bool {
+    path.trim_end_matches(".gz").ends_with(".json")
+}
+
+pub fn lines(path: &str, words: &[(&String, f64)], limit: usize) -> Vec<String> {
+    let sizes = sizes(words, limit);
+    if is_json(path) {
+        let cloud: serde_json::Value = sizes
+            .iter()
+            .map(|(word, size)| json!({ "text": word, "size": size }))
+            .collect();
+        vec![serde_json::to_string_pretty(&cloud).unwrap()]
+    } else {
+        sizes
+            .iter()
+            .map(|(word, size)| format!("{},{}", word, size))
+            .collect()
+    }
+}
@@ -0,0 +1,568 @@
+/*
+ * End to end checks of the harvest binary against pages served from a local socket.
+ *
+ * Each test starts a Site serving a fixed set of pages and recording every path requested from
+ * it, then runs harvest against it with outputs written to a scratch directory.
+ */
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    path::PathBuf,
+    process::{Command, Output},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+struct Site {
+    url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl Site {
+    fn serve(pages: &[(&str, &str)]) -> Site {
+        let responses: Vec<(&str, String)> = pages
+            .iter()
+            .map(|(path, body)| {
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                (*path, response)
+            })
+            .collect();
+        let responses: Vec<(&str, &str)> = responses
+            .iter()
+            .map(|(path, response)| (*path, response.as_str()))
+            .collect();
+        Site::serve_responses(&responses)
+    }
+
+    // Pages given as whole HTTP responses, for status codes and headers of their own
+    fn serve_responses(pages: &[(&str, &str)]) -> Site {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let pages: Vec<(String, String)> = pages
+            .iter()
+            .map(|(path, response)| (path.to_string(), response.to_string()))
+            .collect();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap_or_default();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or_default() > 2 {
+                    line.clear();
+                }
+                let path = request_line
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or("/")
+                    .to_string();
+                let response = match pages.iter().find(|(page, _)| *page == path) {
+                    Some((_, response)) => response.clone(),
+                    None => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+                recorded.lock().unwrap().push(path);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        Site { url, requests }
+    }
+
+    fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+// A SOCKS5 proxy that answers every CONNECT itself with the same page, recording the host names
+// it was asked for
+struct SocksProxy {
+    url: String,
+    hosts: Arc<Mutex<Vec<String>>>,
+}
+
+impl SocksProxy {
+    fn serve(page: &str) -> SocksProxy {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("socks5h://{}", listener.local_addr().unwrap());
+        let page = page.to_string();
+        let hosts = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&hosts);
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                // Greeting: version, number of methods, methods; no authentication is chosen
+                let mut header = [0; 2];
+                let _ = stream.read_exact(&mut header);
+                let mut methods = vec![0; header[1] as usize];
+                let _ = stream.read_exact(&mut methods);
+                let _ = stream.write_all(&[5, 0]);
+                // Request: version, CONNECT, reserved, address type 3 for a host name
+                let mut request = [0; 4];
+                let _ = stream.read_exact(&mut request);
+                if request[3] != 3 {
+                    recorded.lock().unwrap().push("(address)".to_string());
+                    continue;
+                }
+                let mut length = [0; 1];
+                let _ = stream.read_exact(&mut length);
+                let mut host = vec![0; length[0] as usize + 2];
+                let _ = stream.read_exact(&mut host);
+                host.truncate(length[0] as usize);
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&host).into_owned());
+                let _ = stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or_default() > 2 {
+                    line.clear();
+                }
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    page.len(),
+                    page
+                );
+            }
+        });
+        SocksProxy { url, hosts }
+    }
+
+    fn hosts(&self) -> Vec<String> {
+        self.hosts.lock().unwrap().clone()
+    }
+}
+
+// A fresh directory for one run's output files
+fn scratch_dir() -> PathBuf {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "harvest-test-{}-{}",
+        std::process::id(),
+        RUNS.fetch_add(1, Ordering::SeqCst)
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Runs from the crate root, where the common word list is read from
+fn harvest(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_harvest"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn read(path: &PathBuf) -> String {
+    fs::read_to_string(path).unwrap_or_default()
+}
+
+#[test]
+fn entity_encoded_emails_are_extracted() {
+    let site = Site::serve(&[(
+        "/",
+        "<html><body><p>Write to user&#64;example.com or to admin&amp;#64;example.com</p></body></html>",
+    )]);
+    let dir = scratch_dir();
+    let emails = dir.join("emails.txt");
+    harvest(&[
+        &site.url,
+        "--depth",
+        "0",
+        "--email",
+        "--emfile",
+        emails.to_str().unwrap(),
+        "--file",
+        dir.join("words.txt").to_str().unwrap(),
+    ]);
+    let emails = read(&emails);
+    assert!(emails.lines().any(|line| line == "user@example.com"));
+    assert!(emails.lines().any(|line| line == "admin@example.com"));
+}
+
+#[test]
+fn depth_zero_fetches_only_the_seed() {
+    let index = r#"<html><body><a href="/a">a</a> <a href="/b">b</a></body></html>"#;
+    let pages = [("/", index), ("/a", "<p>a</p>"), ("/b", "<p>b</p>")];
+    let dir = scratch_dir();
+    let words = dir.join("words.txt");
+
+    let site = Site::serve(&pages);
+    harvest(&[&site.url, "--depth", "0", "--file", words.to_str().unwrap()]);
+    assert_eq!(site.requests(), ["/"]);
+
+    // The same page at depth 1 does lead on to its links
+    let site = Site::serve(&pages);
+    harvest(&[&site.url, "--depth", "1", "--file", words.to_str().unwrap()]);
+    assert_eq!(site.requests(), ["/", "/a", "/b"]);
+}
+
+#[test]
+fn onion_hosts_are_resolved_by_the_proxy() {
+    let proxy = SocksProxy::serve("<p>hidden hidden hidden hidden</p>");
+    let dir = scratch_dir();
+    let words = dir.join("words.txt");
+    harvest(&[
+        "http://harvestexampleonion.onion/",
+        "--depth",
+        "0",
+        "--proxy",
+        &proxy.url,
+        "--file",
+        words.to_str().unwrap(),
+    ]);
+    assert_eq!(proxy.hosts(), ["harvestexampleonion.onion"]);
+    assert_eq!(read(&words).trim(), "hidden: 4");
+}
+
+#[test]
+fn links_outside_text_elements_are_followed() {
+    // Links used to be looked for only inside text elements such as <p> and <li>, which missed the
+    // bare <nav> link and found the nested one once per enclosing element
+    let index = r#"<html><body>
+        <nav><a href="/nav">nav</a></nav>
+        <ul><li><p>see <a href="/nested">nested</a></p></li></ul>
+        </body></html>"#;
+    let site = Site::serve(&[
+        ("/", index),
+        ("/nav", "<p>nav</p>"),
+        ("/nested", "<p>nested</p>"),
+    ]);
+    let dir = scratch_dir();
+    harvest(&[
+        &site.url,
+        "--depth",
+        "1",
+        "--file",
+        dir.join("words.txt").to_str().unwrap(),
+    ]);
+    let mut requests = site.requests();
+    requests.sort();
+    assert_eq!(requests, ["/", "/nav", "/nested"]);
+}
+
+#[test]
+fn contact_links_are_routed_by_scheme() {
+    let index = r#"<html><body>
+        <a href="mailto:sales@example.com?subject=hi">mail</a>
+        <a href="tel:+1-555-0100;ext=2">call</a>
+        <a href="sms:+15550199">text</a>
+        <a href="https://twitter.com/harvestexample">twitter</a>
+        <a href="javascript:void(0)">script</a>
+        <a href="data:text/html,hello">data</a>
+        <a href="/page?session_id=1">page</a>
+        </body></html>"#;
+    let pages = [("/", index), ("/page?session_id=1", "<p>page</p>")];
+    let site = Site::serve(&pages);
+    let dir = scratch_dir();
+    let emails = dir.join("emails.txt");
+    let phones = dir.join("phones.txt");
+    let socials = dir.join("socials.txt");
+    let params = dir.join("params.txt");
+    harvest(&[
+        &site.url,
+        "--depth",
+        "0",
+        "--email",
+        "--emfile",
+        emails.to_str().unwrap(),
+        "--phone",
+        "--phonefile",
+        phones.to_str().unwrap(),
+        "--social",
+        "--socfile",
+        socials.to_str().unwrap(),
+        "--params",
+        "--paramfile",
+        params.to_str().unwrap(),
+        "--file",
+        dir.join("words.txt").to_str().unwrap(),
+    ]);
+    assert_eq!(
+        read(&emails).lines().collect::<Vec<_>>(),
+        ["sales@example.com"]
+    );
+    assert_eq!(
+        read(&phones).lines().collect::<Vec<_>>(),
+        ["+1-555-0100", "+15550199"]
+    );
+    assert!(read(&socials).contains("harvestexample"));
+    assert_eq!(read(&params).trim(), "session_id");
+    // Links on a page at the depth limit are still recorded, they are only not followed
+    assert_eq!(site.requests(), ["/"]);
+
+    // One hop further only the http link is fetched, the others are not joined onto the site
+    let site = Site::serve(&pages);
+    harvest(&[
+        &site.url,
+        "--depth",
+        "1",
+        "--file",
+        dir.join("words.txt").to_str().unwrap(),
+    ]);
+    assert_eq!(site.requests(), ["/", "/page?session_id=1"]);
+}
+
+#[test]
+fn internal_hosts_are_crawled_through_the_proxy() {
+    let proxy = SocksProxy::serve("<p>intranet intranet intranet intranet</p>");
+    let dir = scratch_dir();
+    let words = dir.join("words.txt");
+    harvest(&[
+        "http://wiki.intranet.corp/",
+        "--depth",
+        "0",
+        "--proxy",
+        &proxy.url,
+        "--file",
+        words.to_str().unwrap(),
+    ]);
+    assert_eq!(proxy.hosts(), ["wiki.intranet.corp"]);
+    assert_eq!(read(&words).trim(), "intranet: 4");
+}
+
+#[test]
+fn unresolved_internal_hosts_are_pointed_out() {
+    let dir = scratch_dir();
+    let output = harvest(&[
+        "http://harvest-test.local/",
+        "--depth",
+        "0",
+        "--file",
+        dir.join("words.txt").to_str().unwrap(),
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("harvest-test.local could not be resolved"));
+    assert!(stderr.contains("socks5h://"));
+}
+
+#[test]
+fn report_words_follow_the_ranking() {
+    let site = Site::serve(&[(
+        "/",
+        "<html><body><h1>zebra zebra zebra zebra</h1><p>apple apple apple apple apple apple apple</p></body></html>",
+    )]);
+    let dir = scratch_dir();
+    let words = dir.join("words.txt");
+    let report = dir.join("report.html");
+    harvest(&[
+        &site.url,
+        "--depth",
+        "0",
+        "--rank",
+        "prominence",
+        "--file",
+        words.to_str().unwrap(),
+        "--report",
+        report.to_str().unwrap(),
+    ]);
+    let listed: Vec<String> = read(&words)
+        .lines()
+        .map(|line| line.split(':').next().unwrap().to_string())
+        .collect();
+    assert_eq!(listed, ["zebra", "apple"]);
+    // Word rows are the ones drawing a bar
+    let reported: Vec<String> = read(&report)
+        .split("<tr><td>")
+        .filter(|row| row.contains("class=\"bar\""))
+        .map(|row| row.split('<').next().unwrap().to_string())
+        .collect();
+    assert_eq!(reported, listed);
+}
+
+#[test]
+fn proxied_hosts_are_not_looked_up_locally() {
+    let proxy = SocksProxy::serve("<p>hidden hidden hidden hidden</p>");
+    let dir = scratch_dir();
+    let words = dir.join("words.txt");
+    // Nothing answers on this name server, so any lookup through it would fail the crawl
+    let output = harvest(&[
+        "http://harvestexampleonion.onion/",
+        "--depth",
+        "0",
+        "--proxy",
+        &proxy.url,
+        "--resolver",
+        "127.0.0.1:9",
+        "--file",
+        words.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert_eq!(proxy.hosts(), ["harvestexampleonion.onion"]);
+    assert_eq!(read(&words).trim(), "hidden: 4");
+}
+
+#[test]
+fn apostrophe_options_change_the_words_counted() {
+    let page = "<p>shan't shan't shan't shan't o'brien o'brien o'brien o'brien</p>";
+    let dir = scratch_dir();
+    let words = dir.join("words.txt");
+    let counted = |option: &str| {
+        let site = Site::serve(&[("/", page)]);
+        harvest(&[
+            &site.url,
+            "--depth",
+            "0",
+            option,
+            "--file",
+            words.to_str().unwrap(),
+        ]);
+        let mut counted: Vec<String> = read(&words).lines().map(String::from).collect();
+        counted.sort();
+        counted
+    };
+    assert_eq!(counted("--strip-apostrophes"), ["obrien: 4", "shant: 4"]);
+    assert_eq!(counted("--split-contractions"), ["o'brien: 4", "shall: 4"]);
+    assert_eq!(
+        counted("--split-contractions=expand"),
+        ["o'brien: 4", "shall: 4"]
+    );
+}
+
+#[test]
+fn only_whole_ok_pages_are_cached() {
+    let dir = scratch_dir();
+    let cache = dir.join("cache");
+    // No Content-Length, so the body is only found to be too long while it is read
+    let long = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nETag: \"long\"\r\nConnection: close\r\n\r\n<p>{}</p>",
+        "cut ".repeat(100)
+    );
+    let missing = "HTTP/1.1 404 Not Found\r\nContent-Type: text/html\r\nETag: \"missing\"\r\nContent-Length: 12\r\nConnection: close\r\n\r\n<p>gone</p>\n";
+    let index = r#"<a href="/long">long</a> <a href="/missing">missing</a>"#;
+    let whole = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nETag: \"index\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        index.len(),
+        index
+    );
+    let site = Site::serve_responses(&[("/", &whole), ("/long", &long), ("/missing", missing)]);
+    harvest(&[
+        &site.url,
+        "--depth",
+        "1",
+        "--max-body-size",
+        "200",
+        "--parse-status",
+        "200,404",
+        "--cache-dir",
+        cache.to_str().unwrap(),
+        "--file",
+        dir.join("words.txt").to_str().unwrap(),
+    ]);
+    let cached: Vec<String> = fs::read_dir(&cache)
+        .unwrap()
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "meta"))
+        .map(|entry| fs::read_to_string(entry.path()).unwrap())
+        .collect();
+    assert_eq!(cached.len(), 1);
+    assert!(cached[0].starts_with(&site.url));
+    assert!(cached[0].contains("\"index\""));
+}
+
+#[test]
+fn profile_words_follow_the_ranking() {
+    let site = Site::serve(&[(
+        "/",
+        "<html><body><h1>zebra zebra zebra zebra</h1><p>apple apple apple apple apple apple apple lemon</p></body></html>",
+    )]);
+    let dir = scratch_dir();
+    let profile = dir.join("profile.txt");
+    harvest(&[
+        &site.url,
+        "--depth",
+        "0",
+        "--rank",
+        "prominence",
+        "--profile",
+        "--file",
+        profile.to_str().unwrap(),
+    ]);
+    // Words listed under the heading are indented, lemon is below the minimum count
+    let profile = read(&profile);
+    let listed: Vec<&str> = profile
+        .lines()
+        .skip_while(|line| *line != "Top words:")
+        .skip(1)
+        .take_while(|line| line.starts_with("  "))
+        .collect();
+    assert_eq!(listed, ["  zebra: 4", "  apple: 7"]);
+}
+
+#[test]
+fn cjk_words_reach_the_wordlist() {
+    let site = Site::serve(&[(
+        "/",
+        "<html><body><p>東京都に住む。東京都。東京都、東京都!</p><p>コーヒー コーヒー コーヒー コーヒー</p></body></html>",
+    )]);
+    let dir = scratch_dir();
+    let words = dir.join("words.txt");
+    harvest(&[&site.url, "--depth", "0", "--file", words.to_str().unwrap()]);
+    let mut listed: Vec<String> = read(&words).lines().map(String::from).collect();
+    listed.sort();
+    assert_eq!(listed, ["コーヒー: 4", "東京都: 4"]);
+}
+
+#[test]
+fn error_statuses_set_the_exit_code() {
+    let dir = scratch_dir();
+    let words = dir.join("words.txt");
+    let exit_code = |site: &Site| {
+        harvest(&[&site.url, "--depth", "1", "--file", words.to_str().unwrap()])
+            .status
+            .code()
+    };
+    let clean = Site::serve(&[("/", r#"<a href="/a">a</a>"#), ("/a", "<p>a</p>")]);
+    assert_eq!(exit_code(&clean), Some(0));
+    // Only the seed is served, so the linked page answers 404
+    let broken_link = Site::serve(&[("/", r#"<a href="/a">a</a>"#)]);
+    assert_eq!(exit_code(&broken_link), Some(1));
+    let missing_seed = Site::serve(&[]);
+    assert_eq!(exit_code(&missing_seed), Some(3));
+}
+
+#[test]
+fn escape_sequences_from_pages_are_not_written_raw() {
+    let page = "<html><body>\
+        <form action=\"/send\x1b[31m\" method=\"post\"><input name=\"user\x1b[31mname\"></form>\
+        <a href=\"https://twitter.com/evil\x1b[31m\">twitter</a>\
+        <p>plain words words words words</p>\
+        </body></html>";
+    let site = Site::serve(&[("/", page)]);
+    let dir = scratch_dir();
+    let forms = dir.join("forms.txt");
+    let socials = dir.join("socials.txt");
+    let words = dir.join("words.txt");
+    harvest(&[
+        &site.url,
+        "--depth",
+        "0",
+        "--forms",
+        "--formfile",
+        forms.to_str().unwrap(),
+        "--social",
+        "--socfile",
+        socials.to_str().unwrap(),
+        "--file",
+        words.to_str().unwrap(),
+    ]);
+    for path in [&forms, &socials, &words] {
+        assert!(!read(path).contains('\x1b'), "{}", read(path));
+    }
+    assert!(read(&forms).contains("user\\u{1b}[31mname"));
+    assert!(read(&socials).contains("evil"));
+}